@@ -1,13 +1,19 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature, TokensConfig, CurrencyId,
+	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, IndicesConfig,
+	SystemConfig, CouncilConfig, CouncilMembershipConfig, DemocracyConfig, WASM_BINARY, Signature, TokensConfig, CurrencyId,
+	AssetRegistryConfig, Balance, ExistentialDeposit,
+	pallet_asset_registry::{AssetMetadata, MAX_SYMBOL_LENGTH},
+	ExchangeConfig, Permill, pallet_exchange::FeeRate,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
-use sc_service::ChainType;
+use sc_service::{ChainType, Properties};
+use serde::{de, Deserialize, Deserializer};
 use serde_json::map::Map;
+use sp_core::crypto::Ss58Codec;
+use std::path::Path;
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -59,8 +65,10 @@ pub fn development_config() -> Result<ChainSpec, String> {
 			vec![
 				authority_keys_from_seed("Alice"),
 			],
-			// Sudo account
-			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			// Council members
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+			],
 			// Pre-funded accounts
 			vec![
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -101,8 +109,12 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				authority_keys_from_seed("Alice"),
 				authority_keys_from_seed("Bob"),
 			],
-			// Sudo account
-			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			// Council members
+			vec![
+				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				get_account_id_from_seed::<sr25519::Public>("Bob"),
+				get_account_id_from_seed::<sr25519::Public>("Charlie"),
+			],
 			// Pre-funded accounts
 			vec![
 				get_account_id_from_seed::<sr25519::Public>("Alice"),
@@ -134,14 +146,100 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+fn asset_metadata(symbol: &str, decimals: u8, existential_deposit: Balance) -> AssetMetadata<Balance> {
+	AssetMetadata {
+		symbol: symbol.as_bytes().to_vec(),
+		decimals,
+		existential_deposit,
+		min_order_size: existential_deposit,
+	}
+}
+
+fn default_assets() -> Vec<(CurrencyId, AssetMetadata<Balance>)> {
+	vec![
+		// the native existential deposit is enforced by `pallet_balances`
+		(CurrencyId::Native, asset_metadata("TEST", 15, ExistentialDeposit::get())),
+		(CurrencyId::DOT, asset_metadata("DOT", 10, 10u128.pow(8))),
+		(CurrencyId::KSM, asset_metadata("KSM", 12, 10u128.pow(9))),
+		(CurrencyId::BTC, asset_metadata("BTC", 8, 10u128.pow(3))),
+	]
+}
+
+const DOT: Balance = 10_000_000_000;
+
+/// A small DOT/BTC book around 0.0005 BTC per DOT: the first account sells DOT at increasing
+/// prices and the second one buys DOT at decreasing prices.
+fn seed_orders(endowed_accounts: &[AccountId]) -> Vec<(AccountId, CurrencyId, Balance, CurrencyId, Balance)> {
+	let (seller, buyer) = match endowed_accounts {
+		[seller, buyer, ..] => (seller, buyer),
+		_ => return vec![],
+	};
+	(1..=5u128)
+		.flat_map(|i| {
+			vec![
+				(seller.clone(), CurrencyId::DOT, 100 * DOT, CurrencyId::BTC, 50_000 * (100 + i)),
+				(buyer.clone(), CurrencyId::BTC, 50_000 * (100 - i), CurrencyId::DOT, 100 * DOT),
+			]
+		})
+		.collect()
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
-	root_key: AccountId,
+	council_members: Vec<AccountId>,
 	endowed_accounts: Vec<AccountId>,
 	_enable_println: bool,
 ) -> GenesisConfig {
+	let endowments = endowed_accounts
+		.iter()
+		.flat_map(|x| {
+			vec![
+				(x.clone(), CurrencyId::Native, 1 << 60),
+				(x.clone(), CurrencyId::DOT, 10u128.pow(16)),
+				(x.clone(), CurrencyId::BTC, 10u128.pow(16)),
+			]
+		})
+		.collect();
+
+	genesis(
+		wasm_binary,
+		initial_authorities,
+		council_members,
+		endowments,
+		default_assets(),
+		ExchangeConfig {
+			listed_pairs: vec![
+				(CurrencyId::Native, CurrencyId::DOT),
+				(CurrencyId::Native, CurrencyId::BTC),
+				(CurrencyId::DOT, CurrencyId::KSM),
+				(CurrencyId::DOT, CurrencyId::BTC),
+				(CurrencyId::KSM, CurrencyId::BTC),
+			],
+			fee_rate: FeeRate {
+				maker: Permill::from_perthousand(1),
+				taker: Permill::from_perthousand(2),
+			},
+			orders: seed_orders(&endowed_accounts),
+		},
+	)
+}
+
+/// Genesis of the runtime, with native endowments going to `pallet_balances` and all the
+/// others to `orml_tokens`.
+fn genesis(
+	wasm_binary: &[u8],
+	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	council_members: Vec<AccountId>,
+	endowments: Vec<(AccountId, CurrencyId, Balance)>,
+	assets: Vec<(CurrencyId, AssetMetadata<Balance>)>,
+	exchange: ExchangeConfig,
+) -> GenesisConfig {
+	let (native, tokens): (Vec<_>, Vec<_>) = endowments
+		.into_iter()
+		.partition(|(_, currency_id, _)| *currency_id == CurrencyId::Native);
+
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
 			// Add Wasm runtime to storage.
@@ -149,8 +247,10 @@ fn testnet_genesis(
 			changes_trie_config: Default::default(),
 		}),
 		pallet_balances: Some(BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances: native.into_iter().map(|(who, _, amount)| (who, amount)).collect(),
+		}),
+		pallet_indices: Some(IndicesConfig {
+			indices: vec![],
 		}),
 		pallet_aura: Some(AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
@@ -158,20 +258,329 @@ fn testnet_genesis(
 		pallet_grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		}),
-		pallet_sudo: Some(SudoConfig {
-			// Assign network admin rights.
-			key: root_key,
+		// the council is initialized by its membership pallet
+		pallet_collective_Instance1: Some(CouncilConfig::default()),
+		pallet_membership_Instance1: Some(CouncilMembershipConfig {
+			members: council_members,
+			phantom: Default::default(),
 		}),
+		pallet_democracy: Some(DemocracyConfig::default()),
 		orml_tokens: Some(TokensConfig {
-			endowed_accounts: endowed_accounts
-			.iter()
-			.flat_map(|x| {
-				vec![
-					(x.clone(), CurrencyId::DOT, 10u128.pow(16)),
-					(x.clone(), CurrencyId::BTC, 10u128.pow(16)),
-				]
-			})
-			.collect(),
+			endowed_accounts: tokens,
+		}),
+		pallet_asset_registry: Some(AssetRegistryConfig {
+			assets,
 		}),
+		pallet_exchange: Some(exchange),
+	}
+}
+
+/// Description of a chain, read from TOML or JSON by the `generate-spec` subcommand.
+///
+/// Keys and accounts are SS58 addresses, or `//Seed` for development keys derived like
+/// `//Alice`. Amounts are plain integers in the smallest unit, or strings for values that do
+/// not fit in a TOML integer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SpecDescription {
+	pub name: String,
+	pub id: String,
+	#[serde(default = "default_chain_type")]
+	pub chain_type: ChainType,
+	pub authorities: Vec<AuthorityDescription>,
+	/// Initial council members, governing the chain. Exclusive with `sudo`.
+	#[serde(default)]
+	pub council: Vec<String>,
+	/// Account governing the chain alone. The runtime has no sudo pallet, so it becomes the only
+	/// council member, whose motions pass on its own vote. Exclusive with `council`.
+	#[serde(default)]
+	pub sudo: Option<String>,
+	#[serde(default)]
+	pub endowments: Vec<EndowmentDescription>,
+	/// Metadata of the currencies in the asset registry, those of the development chains if
+	/// not given
+	#[serde(default)]
+	pub assets: Option<Vec<AssetDescription>>,
+	#[serde(default)]
+	pub exchange: ExchangeDescription,
+	/// Chain properties, e.g. `tokenSymbol`, `tokenDecimals` and `ss58Format`
+	#[serde(default)]
+	pub properties: Option<Properties>,
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AuthorityDescription {
+	pub aura: String,
+	pub grandpa: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EndowmentDescription {
+	pub account: String,
+	pub currency_id: CurrencyId,
+	#[serde(deserialize_with = "deserialize_balance")]
+	pub amount: Balance,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AssetDescription {
+	pub currency_id: CurrencyId,
+	pub symbol: String,
+	pub decimals: u8,
+	#[serde(deserialize_with = "deserialize_balance")]
+	pub existential_deposit: Balance,
+	#[serde(deserialize_with = "deserialize_balance")]
+	pub min_order_size: Balance,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ExchangeDescription {
+	#[serde(default)]
+	pub pairs: Vec<(CurrencyId, CurrencyId)>,
+	/// Maker and taker fees, in parts per million
+	#[serde(default)]
+	pub fees: FeeRate,
+}
+
+fn deserialize_balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Amount {
+		Number(u64),
+		String(String),
+	}
+
+	match Amount::deserialize(deserializer)? {
+		Amount::Number(n) => Ok(n.into()),
+		Amount::String(s) => s.parse().map_err(de::Error::custom),
+	}
+}
+
+fn parse_public<TPublic: Public>(key: &str) -> Result<<TPublic::Pair as Pair>::Public, String> {
+	match key.strip_prefix("//") {
+		Some(seed) => Ok(get_from_seed::<TPublic>(seed)),
+		None => <<TPublic::Pair as Pair>::Public as Ss58Codec>::from_ss58check(key)
+			.map_err(|e| format!("Invalid key {}: {:?}", key, e)),
+	}
+}
+
+fn parse_account(account: &str) -> Result<AccountId, String> {
+	match account.strip_prefix("//") {
+		Some(seed) => Ok(get_account_id_from_seed::<sr25519::Public>(seed)),
+		None => <AccountId as Ss58Codec>::from_ss58check(account)
+			.map_err(|e| format!("Invalid account {}: {:?}", account, e)),
+	}
+}
+
+impl SpecDescription {
+	/// Read a description, as TOML if the file extension is `toml` and JSON otherwise.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let content = std::fs::read_to_string(path)
+			.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+		if path.extension().map_or(false, |ext| ext == "toml") {
+			toml::from_str(&content).map_err(|e| format!("Error parsing {}: {}", path.display(), e))
+		} else {
+			serde_json::from_str(&content).map_err(|e| format!("Error parsing {}: {}", path.display(), e))
+		}
+	}
+
+	/// Build the chain spec described.
+	pub fn into_chain_spec(self) -> Result<ChainSpec, String> {
+		let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+
+		let Genesis { initial_authorities, council_members, endowments, assets } = self.parse()?;
+		let exchange = self.exchange;
+
+		Ok(ChainSpec::from_genesis(
+			&self.name,
+			&self.id,
+			self.chain_type,
+			move || {
+				genesis(
+					wasm_binary,
+					initial_authorities.clone(),
+					council_members.clone(),
+					endowments.clone(),
+					assets.clone(),
+					ExchangeConfig {
+						listed_pairs: exchange.pairs.clone(),
+						fee_rate: exchange.fees,
+						orders: vec![],
+					},
+				)
+			},
+			vec![],
+			None,
+			None,
+			self.properties,
+			None,
+		))
+	}
+
+	/// Parse the keys and accounts, and check the genesis can be built: the runtime's genesis
+	/// panics on invalid values instead of returning an error.
+	fn parse(&self) -> Result<Genesis, String> {
+		if self.authorities.is_empty() {
+			return Err("At least one authority is needed".into());
+		}
+		let initial_authorities = self
+			.authorities
+			.iter()
+			.map(|a| Ok((parse_public::<AuraId>(&a.aura)?, parse_public::<GrandpaId>(&a.grandpa)?)))
+			.collect::<Result<Vec<_>, String>>()?;
+
+		let council_members = match (&self.sudo, self.council.is_empty()) {
+			(Some(key), true) => vec![parse_account(key)?],
+			(None, false) => self
+				.council
+				.iter()
+				.map(|account| parse_account(account))
+				.collect::<Result<Vec<_>, String>>()?,
+			_ => return Err("Either a council or a sudo key is needed, not both".into()),
+		};
+
+		let mut endowments: Vec<(AccountId, CurrencyId, Balance)> = Vec::new();
+		for e in &self.endowments {
+			let account = parse_account(&e.account)?;
+			if endowments.iter().any(|(a, c, _)| *a == account && *c == e.currency_id) {
+				return Err(format!("Duplicate endowment of {} in {:?}", e.account, e.currency_id));
+			}
+			if e.currency_id == CurrencyId::Native && e.amount < ExistentialDeposit::get() {
+				return Err(format!("Endowment of {} is below the existential deposit", e.account));
+			}
+			endowments.push((account, e.currency_id, e.amount));
+		}
+
+		let assets = match &self.assets {
+			None => default_assets(),
+			Some(assets) => {
+				let mut metadata: Vec<(CurrencyId, AssetMetadata<Balance>)> = Vec::new();
+				for asset in assets {
+					if let CurrencyId::Token(_) = asset.currency_id {
+						return Err("Tokens are registered on chain, their ids can't be set at genesis".into());
+					}
+					if metadata.iter().any(|(currency_id, _)| *currency_id == asset.currency_id) {
+						return Err(format!("Duplicate asset {:?}", asset.currency_id));
+					}
+					if asset.symbol.is_empty() || asset.symbol.len() > MAX_SYMBOL_LENGTH {
+						return Err(format!("Invalid symbol of {:?}", asset.currency_id));
+					}
+					metadata.push((asset.currency_id, AssetMetadata {
+						symbol: asset.symbol.as_bytes().to_vec(),
+						decimals: asset.decimals,
+						existential_deposit: asset.existential_deposit,
+						min_order_size: asset.min_order_size,
+					}));
+				}
+				metadata
+			}
+		};
+
+		if let Some((a, _)) = self.exchange.pairs.iter().find(|(a, b)| a == b) {
+			return Err(format!("The pair of {:?} with itself can't be listed", a));
+		}
+
+		Ok(Genesis { initial_authorities, council_members, endowments, assets })
+	}
+}
+
+/// Genesis values of a `SpecDescription`.
+struct Genesis {
+	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	council_members: Vec<AccountId>,
+	endowments: Vec<(AccountId, CurrencyId, Balance)>,
+	assets: Vec<(CurrencyId, AssetMetadata<Balance>)>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn description(extra: &str) -> Result<SpecDescription, String> {
+		let json = format!(
+			r#"{{ "name": "Test", "id": "test", "authorities": [{{ "aura": "//Alice", "grandpa": "//Alice" }}]{} }}"#,
+			extra
+		);
+		serde_json::from_str(&json).map_err(|e| e.to_string())
+	}
+
+	fn parse(extra: &str) -> Result<Genesis, String> {
+		description(extra)?.parse()
+	}
+
+	#[test]
+	fn test_governance() {
+		let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+		let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+
+		let genesis = parse(r#", "council": ["//Alice", "//Bob"]"#).unwrap();
+		assert_eq!(genesis.council_members, vec![alice.clone(), bob]);
+		let genesis = parse(r#", "sudo": "//Alice""#).unwrap();
+		assert_eq!(genesis.council_members, vec![alice]);
+
+		assert!(parse("").is_err());
+		assert!(parse(r#", "council": ["//Alice"], "sudo": "//Alice""#).is_err());
+	}
+
+	#[test]
+	fn test_endowments() {
+		let genesis = parse(
+			r#", "sudo": "//Alice", "endowments": [
+				{ "account": "//Alice", "currencyId": "Native", "amount": "1000000000000000000" },
+				{ "account": "//Alice", "currencyId": "DOT", "amount": 1 }
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(genesis.endowments.len(), 2);
+
+		assert!(parse(
+			r#", "sudo": "//Alice", "endowments": [
+				{ "account": "//Alice", "currencyId": "Native", "amount": "1000000000000000000" },
+				{ "account": "//Alice", "currencyId": "Native", "amount": "1000000000000000000" }
+			]"#,
+		)
+		.is_err());
+		assert!(parse(
+			r#", "sudo": "//Alice", "endowments": [
+				{ "account": "//Alice", "currencyId": "Native", "amount": 0 }
+			]"#,
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn test_assets() {
+		assert_eq!(parse(r#", "sudo": "//Alice""#).unwrap().assets, default_assets());
+
+		let genesis = parse(
+			r#", "sudo": "//Alice", "assets": [
+				{ "currencyId": "DOT", "symbol": "DOT", "decimals": 10, "existentialDeposit": 100, "minOrderSize": 1000 }
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(genesis.assets.len(), 1);
+		assert_eq!(genesis.assets[0].1.min_order_size, 1000);
+
+		assert!(parse(
+			r#", "sudo": "//Alice", "assets": [
+				{ "currencyId": { "Token": 0 }, "symbol": "TKN", "decimals": 10, "existentialDeposit": 1, "minOrderSize": 1 }
+			]"#,
+		)
+		.is_err());
+		assert!(parse(r#", "sudo": "//Alice", "exchange": { "pairs": [["DOT", "DOT"]] }"#).is_err());
+	}
+
+	#[test]
+	fn test_staging_description() {
+		let staging = SpecDescription::from_file(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/res/staging.toml")));
+		assert!(staging.unwrap().parse().is_ok());
 	}
 }
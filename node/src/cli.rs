@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use structopt::StructOpt;
 use sc_cli::RunCmd;
 
@@ -8,6 +9,37 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Replace Aura and GRANDPA with manual sealing, for development and tests:
+	/// `instant` seals a block for every transaction, `manual` only when asked to with the
+	/// `engine_createBlock` RPC, and a number of milliseconds also seals at that interval.
+	#[structopt(long)]
+	pub sealing: Option<Sealing>,
+}
+
+/// How blocks are sealed in `--sealing` mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	Instant,
+	Manual,
+	Interval(u64),
+}
+
+impl std::str::FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			ms => ms
+				.parse()
+				.ok()
+				.filter(|ms| *ms > 0)
+				.map(Sealing::Interval)
+				.ok_or_else(|| format!("Invalid sealing {}, expected instant, manual or a number of milliseconds", s)),
+		}
+	}
 }
 
 #[derive(Debug, StructOpt)]
@@ -17,6 +49,12 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate a chain specification from a TOML or JSON description.
+	GenerateSpec(GenerateSpecCmd),
+
+	/// Export the custom types of the runtime for Polkadot JS.
+	ExportTypes(ExportTypesCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -38,4 +76,50 @@ pub enum Subcommand {
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// Dry run the runtime upgrade against the state of a block, checking the migrations.
+	#[cfg(feature = "try-runtime")]
+	TryRuntime(TryRuntimeCmd),
+
+	/// Dry run the runtime upgrade, needs `--features try-runtime`.
+	#[cfg(not(feature = "try-runtime"))]
+	TryRuntime,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct GenerateSpecCmd {
+	/// Description of the chain, read as TOML if the extension is `.toml` and as JSON otherwise.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	/// Output the raw genesis storage instead of the genesis config.
+	#[structopt(long)]
+	pub raw: bool,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportTypesCmd {
+	/// Write the type definitions to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Fail if the type definitions in this file are not those of the runtime, instead of
+	/// exporting them.
+	#[structopt(long, parse(from_os_str), conflicts_with = "output")]
+	pub check: Option<PathBuf>,
+}
+
+#[cfg(feature = "try-runtime")]
+#[derive(Debug, StructOpt)]
+pub struct TryRuntimeCmd {
+	/// Block whose state the upgrade runs on, the best block if not given.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<sc_cli::BlockNumberOrHash>,
+
+	#[structopt(flatten)]
+	pub shared_params: sc_cli::SharedParams,
 }
@@ -15,8 +15,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{chain_spec, service};
-use crate::cli::{Cli, Subcommand};
+use crate::{chain_spec, service, type_definitions};
+use crate::cli::{Cli, ExportTypesCmd, GenerateSpecCmd, Subcommand};
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
 use node_template_runtime::Block;
@@ -71,11 +71,13 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
+		Some(Subcommand::ExportTypes(cmd)) => cmd.run(),
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, None)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
@@ -83,7 +85,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, None)?;
 				Ok((cmd.run(client, config.database), task_manager))
 			})
 		},
@@ -91,7 +93,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, None)?;
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
@@ -99,7 +101,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, None)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
@@ -111,7 +113,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, backend, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, None)?;
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
@@ -125,15 +127,101 @@ pub fn run() -> sc_cli::Result<()> {
 				You can enable it with `--features runtime-benchmarks`.".into())
 			}
 		},
+		#[cfg(feature = "try-runtime")]
+		Some(Subcommand::TryRuntime(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, ..}
+					= service::new_partial(&config, None)?;
+				Ok((cmd.run(client), task_manager))
+			})
+		},
+		#[cfg(not(feature = "try-runtime"))]
+		Some(Subcommand::TryRuntime) => {
+			Err("Try-runtime wasn't enabled when building the node. \
+			You can enable it with `--features try-runtime`.".into())
+		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let sealing = cli.sealing;
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
 					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					_ => service::new_full(config, sealing),
 				}
 				.map_err(sc_cli::Error::Service)
 			})
 		}
 	}
 }
+
+impl GenerateSpecCmd {
+	/// Build the chain spec described by `input` and write it out.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let spec = chain_spec::SpecDescription::from_file(&self.input)?.into_chain_spec()?;
+		let json = spec.as_json(self.raw)?;
+
+		match &self.output {
+			Some(path) => std::fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "try-runtime")]
+impl sc_cli::CliConfiguration for crate::cli::TryRuntimeCmd {
+	fn shared_params(&self) -> &sc_cli::SharedParams {
+		&self.shared_params
+	}
+}
+
+#[cfg(feature = "try-runtime")]
+impl crate::cli::TryRuntimeCmd {
+	/// Run the runtime upgrade on the state of the `at` block without importing anything.
+	///
+	/// The upgrade runs in the runtime built into the node whenever its spec name matches the
+	/// chain's, so build the node with the runtime to upgrade to.
+	pub async fn run<C>(&self, client: std::sync::Arc<C>) -> sc_cli::Result<()>
+	where
+		C: sp_api::ProvideRuntimeApi<Block> + sp_blockchain::HeaderBackend<Block>,
+		C::Api: node_template_runtime::try_runtime::TryRuntime<Block>,
+	{
+		let at = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => sp_runtime::generic::BlockId::Hash(client.info().best_hash),
+		};
+		let (weight, max_block) = client
+			.runtime_api()
+			.on_runtime_upgrade(&at)
+			.map_err(|e| format!("Runtime upgrade failed at {}: {:?}", at, e))?;
+
+		log::info!(
+			"Runtime upgrade at {} passed its checks, weighing {} of the {} of a block",
+			at,
+			weight,
+			max_block,
+		);
+		Ok(())
+	}
+}
+
+impl ExportTypesCmd {
+	/// Export the type definitions of the runtime, or check those of `check` are up to date.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		if let Some(path) = &self.check {
+			let json = std::fs::read_to_string(path)?;
+			return type_definitions::check(&json).map_err(|e| {
+				let path = path.display();
+				format!("{} in {}, run `export-types --output {}`", e, path, path).into()
+			});
+		}
+
+		let json = type_definitions::to_json(&type_definitions::type_definitions());
+		match &self.output {
+			Some(path) => std::fs::write(path, json + "\n")?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
@@ -1,3 +1,6 @@
 pub mod chain_spec;
+pub mod cli;
 pub mod service;
 pub mod rpc;
+pub mod remote_keystore;
+pub mod type_definitions;
@@ -7,6 +7,8 @@ mod service;
 mod cli;
 mod command;
 mod rpc;
+mod remote_keystore;
+mod type_definitions;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
@@ -0,0 +1,392 @@
+//! Keystore delegating key management and signing to a remote signer.
+//!
+//! Enabled with `--keystore-uri tcp://<host>:<port>`. Every call opens a TCP connection to the
+//! signer, writes one JSON-RPC 2.0 request terminated by a newline and reads one newline
+//! terminated response. Key types and crypto types are their four ASCII characters (e.g. `aura`,
+//! `gran`, and `sr25`, `ed25`, `ecds`), public keys, messages and signatures are `0x` prefixed
+//! hex strings.
+//!
+//! | method                  | params                                 | result                       |
+//! |-------------------------|----------------------------------------|------------------------------|
+//! | `keystore_publicKeys`   | `[key_type, crypto_type]`              | `[public]`                   |
+//! | `keystore_generateNew`  | `[key_type, crypto_type, seed / null]` | `public`                     |
+//! | `keystore_insertUnknown`| `[key_type, suri, public]`             | `null`                       |
+//! | `keystore_keys`         | `[key_type]`                           | `[[crypto_type, public]]`    |
+//! | `keystore_hasKeys`      | `[[[public, key_type]]]`               | `bool`                       |
+//! | `keystore_sign`         | `[key_type, crypto_type, public, msg]` | `signature`                  |
+//!
+//! Errors are returned as JSON-RPC errors and surface as `Error::Other`, a signer that cannot be
+//! reached as `Error::Unavailable`. VRF signing is not part of the protocol, it is only needed by
+//! BABE and this node runs Aura and GRANDPA.
+
+use std::{
+	convert::{TryFrom, TryInto},
+	io::{BufRead, BufReader, Write},
+	net::{TcpStream, ToSocketAddrs},
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::{
+	bytes::{from_hex, to_hex},
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData},
+	CryptoStore, Error, SyncCryptoStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client of a remote signer, see the module documentation for the protocol.
+pub struct RemoteKeystore {
+	address: String,
+	next_id: AtomicU64,
+}
+
+impl RemoteKeystore {
+	/// Create a client for a signer at `tcp://<host>:<port>`. The signer is only contacted when
+	/// keys are needed.
+	pub fn open(uri: &str) -> Result<Self, String> {
+		let address = uri
+			.strip_prefix("tcp://")
+			.ok_or_else(|| format!("Unsupported keystore uri {}, expected tcp://<host>:<port>", uri))?;
+		address
+			.to_socket_addrs()
+			.map_err(|e| format!("Invalid keystore address {}: {}", address, e))?;
+
+		Ok(Self {
+			address: address.to_string(),
+			next_id: AtomicU64::new(0),
+		})
+	}
+
+	fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R, Error> {
+		let request = json!({
+			"jsonrpc": "2.0",
+			"id": self.next_id.fetch_add(1, Ordering::Relaxed),
+			"method": method,
+			"params": params,
+		});
+
+		let unavailable = |e: std::io::Error| {
+			log::warn!(target: "remote-keystore", "{} failed: {}", method, e);
+			Error::Unavailable
+		};
+		let address = self
+			.address
+			.to_socket_addrs()
+			.map_err(unavailable)?
+			.next()
+			.ok_or(Error::Unavailable)?;
+		let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(unavailable)?;
+		stream.set_read_timeout(Some(TIMEOUT)).map_err(unavailable)?;
+		stream.set_write_timeout(Some(TIMEOUT)).map_err(unavailable)?;
+
+		writeln!(stream, "{}", request).map_err(unavailable)?;
+		let mut line = String::new();
+		BufReader::new(stream).read_line(&mut line).map_err(unavailable)?;
+
+		let mut response: Value = serde_json::from_str(&line)
+			.map_err(|e| Error::Other(format!("Invalid response to {}: {}", method, e)))?;
+		if let Some(error) = response.get("error") {
+			return Err(Error::Other(
+				error
+					.get("message")
+					.and_then(Value::as_str)
+					.unwrap_or("unknown error")
+					.to_string(),
+			));
+		}
+		serde_json::from_value(response["result"].take())
+			.map_err(|e| Error::Other(format!("Invalid result of {}: {}", method, e)))
+	}
+
+	fn public_keys<P: for<'a> TryFrom<&'a [u8]>>(&self, id: KeyTypeId, crypto_type: CryptoTypeId) -> Vec<P> {
+		self.call::<Vec<String>>("keystore_publicKeys", json!([key_type(id), crypto_id(crypto_type)]))
+			.unwrap_or_default()
+			.iter()
+			.filter_map(|public| from_hex(public).ok())
+			.filter_map(|public| P::try_from(&public[..]).ok())
+			.collect()
+	}
+
+	fn generate_new(&self, id: KeyTypeId, crypto_type: CryptoTypeId, seed: Option<&str>) -> Result<Vec<u8>, Error> {
+		let public: String = self.call(
+			"keystore_generateNew",
+			json!([key_type(id), crypto_id(crypto_type), seed]),
+		)?;
+		decode_hex(&public)
+	}
+}
+
+/// Encode a key type id as its four characters.
+pub fn key_type(id: KeyTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+/// Encode a crypto type id as its four characters.
+pub fn crypto_id(id: CryptoTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+/// Decode four characters into a key or crypto type id.
+pub fn parse_id(id: &str) -> Result<[u8; 4], Error> {
+	id.as_bytes()
+		.try_into()
+		.map_err(|_| Error::ValidationError(format!("Invalid id {}", id)))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, Error> {
+	from_hex(s).map_err(|e| Error::ValidationError(format!("Invalid hex {}: {:?}", s, e)))
+}
+
+fn decode_public<P: for<'a> TryFrom<&'a [u8]>>(public: Vec<u8>) -> Result<P, Error> {
+	P::try_from(&public[..]).map_err(|_| Error::ValidationError("Invalid public key".into()))
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+	}
+
+	fn sr25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<sr25519::Public, Error> {
+		decode_public(self.generate_new(id, sr25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+	}
+
+	fn ed25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ed25519::Public, Error> {
+		decode_public(self.generate_new(id, ed25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+	}
+
+	fn ecdsa_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ecdsa::Public, Error> {
+		decode_public(self.generate_new(id, ecdsa::CRYPTO_ID, seed)?)
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call::<Value>("keystore_insertUnknown", json!([key_type(id), suri, to_hex(public, false)]))
+			.map(|_| ())
+			.map_err(|_| ())
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let all_keys = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| all_keys.contains(key)).collect())
+	}
+
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.call::<Vec<(String, String)>>("keystore_keys", json!([key_type(id)]))?
+			.into_iter()
+			.map(|(crypto_type, public)| {
+				Ok(CryptoTypePublicPair(CryptoTypeId(parse_id(&crypto_type)?), decode_hex(&public)?))
+			})
+			.collect()
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys
+			.iter()
+			.map(|(public, id)| (to_hex(public, false), key_type(*id)))
+			.collect::<Vec<_>>();
+		self.call("keystore_hasKeys", json!([public_keys])).unwrap_or(false)
+	}
+
+	fn sign_with(&self, id: KeyTypeId, key: &CryptoTypePublicPair, msg: &[u8]) -> Result<Vec<u8>, Error> {
+		let signature: String = self.call(
+			"keystore_sign",
+			json!([key_type(id), crypto_id(key.0), to_hex(&key.1, false), to_hex(msg, false)]),
+		)?;
+		decode_hex(&signature)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &sr25519::Public,
+		_transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, Error> {
+		Err(Error::Unavailable)
+	}
+}
+
+/// The signer is called synchronously, the asynchronous interface blocks on it.
+#[async_trait::async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		SyncCryptoStore::sr25519_public_keys(self, id)
+	}
+
+	async fn sr25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<sr25519::Public, Error> {
+		SyncCryptoStore::sr25519_generate_new(self, id, seed)
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		SyncCryptoStore::ed25519_public_keys(self, id)
+	}
+
+	async fn ed25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ed25519::Public, Error> {
+		SyncCryptoStore::ed25519_generate_new(self, id, seed)
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		SyncCryptoStore::ecdsa_public_keys(self, id)
+	}
+
+	async fn ecdsa_generate_new(&self, id: KeyTypeId, seed: Option<&str>) -> Result<ecdsa::Public, Error> {
+		SyncCryptoStore::ecdsa_generate_new(self, id, seed)
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		SyncCryptoStore::insert_unknown(self, id, suri, public)
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		SyncCryptoStore::supported_keys(self, id, keys)
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		SyncCryptoStore::keys(self, id)
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		SyncCryptoStore::has_keys(self, public_keys)
+	}
+
+	async fn sign_with(&self, id: KeyTypeId, key: &CryptoTypePublicPair, msg: &[u8]) -> Result<Vec<u8>, Error> {
+		SyncCryptoStore::sign_with(self, id, key, msg)
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, Error> {
+		SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_keystore::LocalKeystore;
+	use sp_core::{crypto::Pair, testing::SR25519};
+	use std::{net::TcpListener, thread};
+
+	/// Stand-in signer serving the protocol from an in-memory keystore.
+	fn spawn_signer() -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+		let keystore = LocalKeystore::in_memory();
+
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = stream.unwrap();
+				let mut line = String::new();
+				BufReader::new(&stream).read_line(&mut line).unwrap();
+				let request: Value = serde_json::from_str(&line).unwrap();
+				let response = match serve(&keystore, request["method"].as_str().unwrap(), &request["params"]) {
+					Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+					Err(e) => json!({
+						"jsonrpc": "2.0",
+						"id": request["id"],
+						"error": { "code": -32000, "message": e.to_string() },
+					}),
+				};
+				writeln!(stream, "{}", response).unwrap();
+			}
+		});
+
+		format!("tcp://{}", address)
+	}
+
+	fn serve(keystore: &LocalKeystore, method: &str, params: &Value) -> Result<Value, Error> {
+		let id = |i: usize| parse_id(params[i].as_str().unwrap());
+		let hex = |i: usize| decode_hex(params[i].as_str().unwrap());
+		match method {
+			"keystore_generateNew" => {
+				assert_eq!(params[1], "sr25");
+				let public = SyncCryptoStore::sr25519_generate_new(keystore, KeyTypeId(id(0)?), params[2].as_str())?;
+				Ok(json!(to_hex(&public.0, false)))
+			}
+			"keystore_publicKeys" => {
+				let keys = SyncCryptoStore::sr25519_public_keys(keystore, KeyTypeId(id(0)?));
+				Ok(json!(keys.iter().map(|k| to_hex(&k.0, false)).collect::<Vec<_>>()))
+			}
+			"keystore_keys" => {
+				let keys = SyncCryptoStore::keys(keystore, KeyTypeId(id(0)?))?;
+				Ok(json!(keys
+					.iter()
+					.map(|k| (crypto_id(k.0), to_hex(&k.1, false)))
+					.collect::<Vec<_>>()))
+			}
+			"keystore_sign" => {
+				let key = CryptoTypePublicPair(CryptoTypeId(id(1)?), hex(2)?);
+				let signature = SyncCryptoStore::sign_with(keystore, KeyTypeId(id(0)?), &key, &hex(3)?)?;
+				Ok(json!(to_hex(&signature, false)))
+			}
+			_ => Err(Error::Other(format!("Unknown method {}", method))),
+		}
+	}
+
+	#[test]
+	fn test_sign_with_remote_key() {
+		let keystore = RemoteKeystore::open(&spawn_signer()).unwrap();
+
+		let public = SyncCryptoStore::sr25519_generate_new(&keystore, SR25519, None).unwrap();
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&keystore, SR25519), vec![public]);
+		assert_eq!(
+			SyncCryptoStore::keys(&keystore, SR25519).unwrap(),
+			vec![CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec())]
+		);
+
+		let signature = SyncCryptoStore::sign_with(
+			&keystore,
+			SR25519,
+			&CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec()),
+			b"message",
+		)
+		.unwrap();
+		let signature = sr25519::Signature::try_from(&signature[..]).unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"message", &public));
+	}
+
+	#[test]
+	fn test_remote_errors() {
+		assert!(RemoteKeystore::open("http://127.0.0.1:9999").is_err());
+
+		let keystore = RemoteKeystore::open(&spawn_signer()).unwrap();
+		assert!(!SyncCryptoStore::has_keys(&keystore, &[]));
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&keystore, SR25519, &CryptoTypePublicPair(sr25519::CRYPTO_ID, vec![0; 32]), b""),
+			Err(Error::Other(_))
+		));
+
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+		drop(listener);
+		let keystore = RemoteKeystore::open(&format!("tcp://{}", address)).unwrap();
+		assert!(matches!(
+			SyncCryptoStore::sr25519_generate_new(&keystore, SR25519, None),
+			Err(Error::Unavailable)
+		));
+	}
+}
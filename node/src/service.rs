@@ -1,17 +1,20 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use futures::SinkExt;
 use sc_client_api::{ExecutorProvider, RemoteBackend};
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use sc_consensus_manual_seal::{EngineCommand, InstantSealParams, ManualSealParams};
+use node_template_runtime::{self, opaque::Block, RuntimeApi, SLOT_DURATION};
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
-use sp_inherents::InherentDataProviders;
+use sp_inherents::{InherentData, InherentDataProviders, InherentIdentifier, ProvideInherentData};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_finality_grandpa::SharedVoterState;
-use sc_keystore::LocalKeystore;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-
+use crate::cli::Sealing;
+use crate::remote_keystore::RemoteKeystore;
 
 
 // Our native executor instance.
@@ -26,8 +29,38 @@ type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
+/// Provides timestamps one slot apart, so that manual sealing can produce blocks faster than the
+/// minimum period between blocks enforced by the runtime.
+pub struct MockTimestampInherentDataProvider(AtomicU64);
+
+impl MockTimestampInherentDataProvider {
+	fn new() -> Self {
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("current time is after the unix epoch; qed");
+		Self(AtomicU64::new(now.as_millis() as u64))
+	}
+}
+
+impl ProvideInherentData for MockTimestampInherentDataProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&sp_timestamp::INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), sp_inherents::Error> {
+		let timestamp = self.0.fetch_add(SLOT_DURATION, Ordering::SeqCst);
+		inherent_data.put_data(sp_timestamp::INHERENT_IDENTIFIER, &timestamp)
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		sp_timestamp::InherentError::try_from(&sp_timestamp::INHERENT_IDENTIFIER, error)
+			.map(|e| format!("{:?}", e))
+	}
+}
+
 pub fn new_partial(
 	config: &Configuration,
+	sealing: Option<Sealing>,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
@@ -52,11 +85,6 @@ pub fn new_partial(
 	>,
 	ServiceError,
 > {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(format!(
-			"Remote Keystores are not supported."
-		)));
-	}
 	let inherent_data_providers = InherentDataProviders::new();
 
 	let (client, backend, keystore_container, task_manager) =
@@ -84,16 +112,28 @@ pub fn new_partial(
 		client.clone(),
 	);
 
-	let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
-		sc_consensus_aura::slot_duration(&*client)?,
-		aura_block_import.clone(),
-		Some(Box::new(grandpa_block_import.clone())),
-		client.clone(),
-		inherent_data_providers.clone(),
-		&task_manager.spawn_handle(),
-		config.prometheus_registry(),
-		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
-	)?;
+	let import_queue = if sealing.is_some() {
+		inherent_data_providers
+			.register_provider(MockTimestampInherentDataProvider::new())
+			.map_err(|e| ServiceError::Other(format!("{:?}", e)))?;
+
+		sc_consensus_manual_seal::import_queue(
+			Box::new(client.clone()),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+		)
+	} else {
+		sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
+			sc_consensus_aura::slot_duration(&*client)?,
+			aura_block_import.clone(),
+			Some(Box::new(grandpa_block_import.clone())),
+			client.clone(),
+			inherent_data_providers.clone(),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+		)?
+	};
 
 	Ok(sc_service::PartialComponents {
 		client,
@@ -108,15 +148,12 @@ pub fn new_partial(
 	})
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//		must return a concrete type (NOT `LocalKeystore`) that
-	//		implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+fn remote_keystore(url: &String) -> Result<Arc<RemoteKeystore>, String> {
+	RemoteKeystore::open(url).map(Arc::new)
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+/// Builds a new service for a full client, sealing blocks manually if `sealing` is set.
+pub fn new_full(mut config: Configuration, sealing: Option<Sealing>) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -127,7 +164,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		transaction_pool,
 		inherent_data_providers,
 		other: (block_import, grandpa_link),
-	} = new_partial(&config)?;
+	} = new_partial(&config, sealing)?;
 
 	if let Some(url) = &config.keystore_remote {
 		match remote_keystore(url) {
@@ -174,15 +211,26 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	// instant sealing is driven by the transaction pool, the other modes by engine commands
+	let (command_sink, commands_stream) = match sealing {
+		Some(Sealing::Manual) | Some(Sealing::Interval(_)) => {
+			let (sink, stream) = futures::channel::mpsc::channel(1024);
+			(Some(sink), Some(stream))
+		}
+		_ => (None, None),
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let command_sink = command_sink.clone();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
 				pool: pool.clone(),
 				deny_unsafe,
+				command_sink: command_sink.clone(),
 			};
 
 			crate::rpc::create_full(deps)
@@ -205,6 +253,68 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			config,
 		})?;
 
+	if let Some(sealing) = sealing {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+		);
+
+		match (command_sink, commands_stream) {
+			(Some(mut command_sink), Some(commands_stream)) => {
+				if let Sealing::Interval(ms) = sealing {
+					task_manager.spawn_handle().spawn("manual-seal-interval", async move {
+						loop {
+							futures_timer::Delay::new(Duration::from_millis(ms)).await;
+							let command = EngineCommand::SealNewBlock {
+								create_empty: true,
+								finalize: true,
+								parent_hash: None,
+								sender: None,
+							};
+							if command_sink.send(command).await.is_err() {
+								break;
+							}
+						}
+					});
+				}
+
+				let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+					block_import: client.clone(),
+					env: proposer_factory,
+					client: client.clone(),
+					pool: transaction_pool.pool().clone(),
+					commands_stream,
+					select_chain,
+					consensus_data_provider: None,
+					inherent_data_providers,
+				});
+				task_manager
+					.spawn_essential_handle()
+					.spawn_blocking("manual-seal", manual_seal);
+			}
+			_ => {
+				let instant_seal = sc_consensus_manual_seal::run_instant_seal(InstantSealParams {
+					block_import: client.clone(),
+					env: proposer_factory,
+					client: client.clone(),
+					pool: transaction_pool.pool().clone(),
+					select_chain,
+					consensus_data_provider: None,
+					inherent_data_providers,
+				});
+				task_manager
+					.spawn_essential_handle()
+					.spawn_blocking("instant-seal", instant_seal);
+			}
+		}
+
+		// no Aura or GRANDPA, the sealing task produces and finalizes blocks on its own
+		network_starter.start_network();
+		return Ok(task_manager);
+	}
+
 	if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
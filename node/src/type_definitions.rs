@@ -0,0 +1,457 @@
+//! Custom type definitions for Polkadot JS, derived from the runtime's types.
+//!
+//! Structs and enums are described by their `scale_info::TypeInfo`, with field types named as in
+//! the Rust source, so `Order<CurrencyId, Balance, AccountId, BlockNumber>` gives an `Order` whose
+//! `owner` is an `AccountId`. They are instantiated with placeholder parameters, which only matter
+//! for the encoding of `#[codec(compact)]` fields. Primitive types and aliases are named from the
+//! runtime configuration. The runtime's own signed extensions are listed under `signedExtensions`.
+//!
+//! `check` also walks the runtime metadata, so that a type used by a call, event, storage item or
+//! constant that Polkadot JS doesn't know is reported until it is defined here.
+
+use node_template_runtime::{
+	pallet_asset_registry::{self, AssetMetadata},
+	pallet_exchange::{self, DelegatePermissions, FeeRate, FeeTier, Fill, Order, Releases, SignedOrder},
+	pallet_fee_payment::ChargeFeeIn,
+	pallet_multi_treasury::{self, SpendProposal},
+	pallet_rewards::PeriodReward,
+	Address, AccountIndex, Amount, CurrencyId, Runtime, Signature,
+};
+use codec::{Decode, Encode};
+use frame_metadata::{
+	DecodeDifferent, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV12,
+	StorageEntryType,
+};
+use scale_info::{form::MetaForm, Field, TypeDef, TypeInfo};
+use sp_runtime::traits::SignedExtension;
+use serde_json::{json, Map, Value};
+
+/// Custom types of the runtime, as they must appear in `types.json`.
+pub fn type_definitions() -> Map<String, Value> {
+	let mut types = Types::default();
+
+	types.alias("Address", short_name::<Address>());
+	types.alias("LookupSource", short_name::<Address>());
+	types.alias("AccountIndex", short_name::<AccountIndex>());
+	types.define::<CurrencyId>();
+	types.alias("CurrencyIdOf", "CurrencyId");
+	types.alias("TokenId", short_name::<pallet_asset_registry::TokenId>());
+	types.alias("Amount", short_name::<Amount>());
+	types.alias("AmountOf", "Amount");
+	types.define::<AssetMetadata<u128>>();
+
+	types.define::<Order<u32, u128, u64, u32>>();
+	types.alias("OrderOf", "Order");
+	types.alias("OrderId", short_name::<<Runtime as pallet_exchange::Config>::OrderId>());
+	types.alias("PairOf", format!("({0}, {0})", short_name::<CurrencyId>()));
+	types.define::<Fill<u64, u32, u32, u128>>();
+	types.define::<FeeRate>();
+	types.define::<FeeTier<u128>>();
+	types.define::<DelegatePermissions<u32, u128>>();
+	types.alias("DelegatePermissionsOf", "DelegatePermissions");
+	types.define::<Releases>();
+	types.define::<SignedOrder<u32, u128, u64, u32>>();
+	types.alias("SignedOrderOf", "SignedOrder");
+	types.alias("OffchainSignature", short_name::<Signature>());
+
+	types.define::<PeriodReward<u128>>();
+
+	types.alias("ProposalIndex", short_name::<pallet_multi_treasury::ProposalIndex>());
+	types.define::<SpendProposal<u64, u32, u128>>();
+	types.alias("SpendProposalOf", "SpendProposal");
+
+	let mut definitions = types.resolve();
+	definitions.insert("signedExtensions".to_string(), signed_extensions());
+	definitions
+}
+
+/// Signed extensions of the runtime unknown to Polkadot JS, with the fields they add to the
+/// extrinsic and to the signed payload.
+fn signed_extensions() -> Value {
+	json!({
+		(ChargeFeeIn::<Runtime>::IDENTIFIER): {
+			"extrinsic": {
+				"tip": "Compact<Balance>",
+				"currencyId": format!("Option<{}>", short_name::<CurrencyId>())
+			},
+			"payload": {}
+		}
+	})
+}
+
+/// Modules of the runtime whose types Polkadot JS ships with, and aren't checked.
+const UPSTREAM_MODULES: &[&str] = &[
+	"System",
+	"RandomnessCollectiveFlip",
+	"Timestamp",
+	"Aura",
+	"Grandpa",
+	"Balances",
+	"TransactionPayment",
+	"Council",
+	"CouncilMembership",
+	"Scheduler",
+	"Democracy",
+	"Indices",
+];
+
+/// Types known to Polkadot JS that the other modules use.
+const KNOWN_TYPES: &[&str] = &[
+	"bool",
+	"u8",
+	"u16",
+	"u32",
+	"u64",
+	"u128",
+	"i8",
+	"i16",
+	"i32",
+	"i64",
+	"i128",
+	"Vec",
+	"Option",
+	"Compact",
+	"AccountId",
+	"AccountData",
+	"Balance",
+	"BalanceLock",
+	"BalanceOf",
+	"BlockNumber",
+	"EcdsaSignature",
+	"FixedU128",
+	"H160",
+	"H256",
+	"Hash",
+	"LookupSource",
+	"ModuleId",
+	"Permill",
+	"TransactionPriority",
+];
+
+/// Signed extensions known to Polkadot JS.
+const KNOWN_SIGNED_EXTENSIONS: &[&str] = &[
+	"CheckSpecVersion",
+	"CheckTxVersion",
+	"CheckGenesis",
+	"CheckMortality",
+	"CheckNonce",
+	"CheckWeight",
+	"ChargeTransactionPayment",
+];
+
+/// Compare the definitions in `json` with `type_definitions`, listing the types that differ,
+/// including in the order of their fields, and the types of the runtime metadata that are
+/// neither known to Polkadot JS nor defined.
+pub fn check(json: &str) -> Result<(), String> {
+	let checked: Map<String, Value> =
+		serde_json::from_str(json).map_err(|e| format!("Invalid types JSON: {}", e))?;
+	let expected = type_definitions();
+
+	let undefined = undefined_types(&expected);
+	if !undefined.is_empty() {
+		return Err(format!("Undefined types: {}", undefined.join(", ")));
+	}
+
+	let mut stale = expected
+		.iter()
+		.filter(|(name, definition)| {
+			checked.get(*name).map(Value::to_string) != Some(definition.to_string())
+		})
+		.map(|(name, _)| name.clone())
+		.chain(checked.keys().filter(|name| !expected.contains_key(*name)).cloned())
+		.collect::<Vec<_>>();
+	stale.sort();
+
+	if stale.is_empty() {
+		Ok(())
+	} else {
+		Err(format!("Stale type definitions: {}", stale.join(", ")))
+	}
+}
+
+/// Names used by the runtime metadata, outside `UPSTREAM_MODULES`, that are neither in
+/// `KNOWN_TYPES` nor in `definitions`, and signed extensions missing from its `signedExtensions`.
+fn undefined_types(definitions: &Map<String, Value>) -> Vec<String> {
+	let metadata = runtime_metadata();
+
+	let mut undefined = decoded(&metadata.modules)
+		.iter()
+		.filter(|module| !UPSTREAM_MODULES.contains(&decoded(&module.name).as_str()))
+		.flat_map(module_types)
+		.flat_map(|ty| type_names(&ty))
+		.filter(|name| !KNOWN_TYPES.contains(&name.as_str()) && !definitions.contains_key(name))
+		.collect::<Vec<_>>();
+
+	let extensions = definitions.get("signedExtensions").and_then(Value::as_object);
+	undefined.extend(
+		metadata
+			.extrinsic
+			.signed_extensions
+			.iter()
+			.map(|identifier| decoded(identifier).clone())
+			.filter(|identifier| {
+				!KNOWN_SIGNED_EXTENSIONS.contains(&identifier.as_str())
+					&& !extensions.map_or(false, |extensions| extensions.contains_key(identifier))
+			}),
+	);
+
+	undefined.sort();
+	undefined.dedup();
+	undefined
+}
+
+/// The runtime metadata, decoded so that its strings are owned.
+fn runtime_metadata() -> RuntimeMetadataV12 {
+	let encoded = Runtime::metadata().encode();
+	match RuntimeMetadataPrefixed::decode(&mut &encoded[..]) {
+		Ok(RuntimeMetadataPrefixed(_, RuntimeMetadata::V12(metadata))) => metadata,
+		_ => panic!("the runtime metadata decodes as V12"),
+	}
+}
+
+fn decoded<B: 'static, O: 'static>(value: &DecodeDifferent<B, O>) -> &O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("the metadata was decoded"),
+	}
+}
+
+/// Types of the storage items, call arguments, event arguments and constants of `module`, as
+/// written in its source.
+fn module_types(module: &ModuleMetadata) -> Vec<String> {
+	let mut types = Vec::new();
+	if let Some(storage) = &module.storage {
+		for entry in decoded(&decoded(storage).entries) {
+			match &entry.ty {
+				StorageEntryType::Plain(value) => types.push(decoded(value).clone()),
+				StorageEntryType::Map { key, value, .. } => {
+					types.extend(vec![decoded(key).clone(), decoded(value).clone()])
+				}
+				StorageEntryType::DoubleMap { key1, key2, value, .. } => types.extend(vec![
+					decoded(key1).clone(),
+					decoded(key2).clone(),
+					decoded(value).clone(),
+				]),
+			}
+		}
+	}
+	for call in module.calls.iter().flat_map(decoded) {
+		types.extend(decoded(&call.arguments).iter().map(|arg| decoded(&arg.ty).clone()));
+	}
+	for event in module.event.iter().flat_map(decoded) {
+		types.extend(decoded(&event.arguments).iter().cloned());
+	}
+	types.extend(decoded(&module.constants).iter().map(|constant| decoded(&constant.ty).clone()));
+	types
+}
+
+/// Names in `ty` as Polkadot JS reads them, without trait casts, paths, lifetimes, array lengths
+/// and the `T`, `I` and `Self` parameters.
+fn type_names(ty: &str) -> Vec<String> {
+	let mut ty = ty.replace("<T::Lookup as StaticLookup>::Source", "LookupSource");
+	while let Some(start) = ty.find("<T as ") {
+		match ty[start..].find(">::") {
+			Some(end) => ty.replace_range(start..start + end + 3, ""),
+			None => break,
+		}
+	}
+
+	let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+	let mut names = Vec::new();
+	let mut rest = ty.as_str();
+	while let Some(start) = rest.find(is_ident) {
+		let lifetime = rest[..start].ends_with('\'');
+		rest = &rest[start..];
+		let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+		let (name, after) = rest.split_at(end);
+		rest = after;
+
+		if !lifetime
+			&& !after.starts_with("::")
+			&& !name.starts_with(|c: char| c.is_ascii_digit())
+			&& !["T", "I", "Self"].contains(&name)
+		{
+			names.push(name.to_string());
+		}
+	}
+	names
+}
+
+/// Write `definitions` as JSON in the layout of `types.json`.
+pub fn to_json(definitions: &Map<String, Value>) -> String {
+	let mut json = Vec::new();
+	let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+	let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+	serde::Serialize::serialize(definitions, &mut serializer).expect("a JSON map serializes");
+	String::from_utf8(json).expect("serde_json writes UTF-8")
+}
+
+enum Definition {
+	Alias(&'static str, String),
+	Type(scale_info::Type<MetaForm>),
+}
+
+/// Definitions in the order they are added, which is kept in the JSON.
+#[derive(Default)]
+struct Types(Vec<Definition>);
+
+impl Types {
+	fn alias(&mut self, name: &'static str, definition: impl Into<String>) {
+		self.0.push(Definition::Alias(name, definition.into()));
+	}
+
+	fn define<T: TypeInfo + 'static>(&mut self) {
+		self.0.push(Definition::Type(T::type_info()));
+	}
+
+	fn resolve(self) -> Map<String, Value> {
+		let custom = self
+			.0
+			.iter()
+			.filter_map(|definition| match definition {
+				Definition::Type(ty) => Some(type_name(ty)),
+				Definition::Alias(..) => None,
+			})
+			.collect::<Vec<_>>();
+
+		let mut types = Map::new();
+		for definition in &self.0 {
+			match definition {
+				Definition::Alias(name, definition) => {
+					types.insert(name.to_string(), definition.clone().into());
+				}
+				Definition::Type(ty) => {
+					types.insert(type_name(ty).to_string(), type_definition(ty, &custom));
+				}
+			}
+		}
+		types
+	}
+}
+
+fn type_name(ty: &scale_info::Type<MetaForm>) -> &'static str {
+	ty.path().ident().expect("custom types are named")
+}
+
+/// Structs as their fields, and enums as `_enum` with their variants.
+fn type_definition(ty: &scale_info::Type<MetaForm>, custom: &[&str]) -> Value {
+	match ty.type_def() {
+		TypeDef::Composite(composite) => fields(composite.fields(), custom),
+		// enums without data are listed by name only
+		TypeDef::Variant(variant) if variant.variants().iter().all(|v| v.fields().is_empty()) => {
+			let names = variant.variants().iter().map(|v| *v.name()).collect::<Vec<_>>();
+			json!({ "_enum": names })
+		}
+		TypeDef::Variant(variant) => {
+			let variants = variant
+				.variants()
+				.iter()
+				.map(|v| (v.name().to_string(), fields(v.fields(), custom)))
+				.collect::<Map<_, _>>();
+			json!({ "_enum": variants })
+		}
+		_ => panic!("{:?} is neither a struct nor an enum", ty.path()),
+	}
+}
+
+/// Named fields as an object, one unnamed field as its type, several as a tuple and none as `Null`.
+fn fields(fields: &[Field<MetaForm>], custom: &[&str]) -> Value {
+	let types = fields.iter().map(|field| field_type(field, custom));
+	match fields {
+		[] => "Null".into(),
+		[field] if field.name().is_none() => field_type(field, custom).into(),
+		_ if fields[0].name().is_none() => {
+			format!("({})", types.collect::<Vec<_>>().join(", ")).into()
+		}
+		_ => fields
+			.iter()
+			.zip(types)
+			.map(|(field, ty)| (field.name().expect("fields are all named").to_string(), ty.into()))
+			.collect::<Map<_, Value>>()
+			.into(),
+	}
+}
+
+/// Name of the type of `field` as written in the source, without module paths and without the
+/// parameters of the `custom` types.
+fn field_type(field: &Field<MetaForm>, custom: &[&str]) -> String {
+	let name = short_name_of(field.type_name());
+	let name = match name.find('<') {
+		Some(i) if custom.contains(&&name[..i]) => name[..i].to_string(),
+		_ => name,
+	};
+	match field.ty().type_info().type_def() {
+		TypeDef::Compact(_) => format!("Compact<{}>", name),
+		_ => name,
+	}
+}
+
+/// Name of `T` without module paths nor parameters, such as `MultiAddress` for the `Address`.
+fn short_name<T>() -> String {
+	let name = short_name_of(std::any::type_name::<T>());
+	match name.find('<') {
+		Some(i) => name[..i].to_string(),
+		None => name,
+	}
+}
+
+/// `name` without whitespace and module paths, with a space after commas.
+fn short_name_of(name: &str) -> String {
+	let mut short = String::new();
+	let mut segment = String::new();
+	for c in name.chars().filter(|c| !c.is_whitespace()) {
+		if c.is_alphanumeric() || c == '_' || c == ':' {
+			segment.push(c);
+			continue;
+		}
+		short.push_str(segment.rsplit("::").next().unwrap_or_default());
+		segment.clear();
+		short.push(c);
+		if c == ',' {
+			short.push(' ');
+		}
+	}
+	short.push_str(segment.rsplit("::").next().unwrap_or_default());
+	short
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_types_json_is_up_to_date() {
+		if let Err(e) = check(include_str!("../../types.json")) {
+			panic!("{}, run `cargo run -- export-types --output types.json`", e);
+		}
+	}
+
+	#[test]
+	fn test_metadata_types_are_defined() {
+		assert_eq!(undefined_types(&type_definitions()), Vec::<String>::new());
+	}
+
+	#[test]
+	fn test_type_names() {
+		assert_eq!(
+			type_names("Vec<(T::BlockNumber, BalanceOf<T>)>"),
+			vec!["Vec", "BlockNumber", "BalanceOf"]
+		);
+		assert_eq!(
+			type_names("<T as frame_system::Config>::AccountId"),
+			vec!["AccountId"]
+		);
+		assert_eq!(type_names("<T::Lookup as StaticLookup>::Source"), vec!["LookupSource"]);
+		assert_eq!(type_names("&'static [u8]"), vec!["u8"]);
+		assert_eq!(type_names("[u8; 32]"), vec!["u8"]);
+	}
+
+	#[test]
+	fn test_short_names() {
+		assert_eq!(short_name::<Address>(), "MultiAddress");
+		assert_eq!(
+			short_name_of("Option < Vec < (a::CurrencyId , Balance) > >"),
+			"Option<Vec<(CurrencyId, Balance)>>"
+		);
+	}
+}
@@ -0,0 +1,191 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Registry of the currencies known to the chain.
+//!
+//! Governance registers new tokens with `register_asset`, which allocates the next token id, so
+//! listing a currency does not need a runtime upgrade. Currencies built into `CurrencyId` get
+//! their metadata at genesis or through `update_asset`. Other pallets read the metadata through
+//! the getters of this pallet, e.g. for existential deposits and minimum order sizes. The
+//! existential deposit of a token is fixed once any of it has been issued.
+
+use codec::{Decode, Encode};
+use frame_support::sp_runtime::{
+	traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member, Zero},
+	RuntimeDebug,
+};
+use orml_traits::MultiCurrency;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::vec::Vec;
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, scale_info::TypeInfo))]
+pub struct AssetMetadata<Balance> {
+	pub symbol: Vec<u8>,
+	pub decimals: u8,
+	/// Minimum balance an account must hold to exist
+	pub existential_deposit: Balance,
+	/// Minimum amount of either side of an exchange order
+	pub min_order_size: Balance,
+}
+
+/// Maximum length of an asset symbol.
+pub const MAX_SYMBOL_LENGTH: usize = 16;
+
+pub type TokenId = u32;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Currency identifier, registered tokens are created from their `TokenId`
+		type CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize + From<TokenId>;
+		/// Balance
+		type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+		/// Origin allowed to register and update assets
+		type RegisterOrigin: EnsureOrigin<Self::Origin>;
+		/// Currency, whose issuance keeps the existential deposit of a token from changing
+		type Currency: MultiCurrency<
+			Self::AccountId,
+			CurrencyId = Self::CurrencyId,
+			Balance = Self::Balance,
+		>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	#[pallet::getter(fn asset_metadatas)]
+	pub type AssetMetadatas<T: Config> =
+		StorageMap<_, Twox64Concat, T::CurrencyId, AssetMetadata<T::Balance>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn next_token_id)]
+	pub type NextTokenId<T: Config> = StorageValue<_, TokenId, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub assets: Vec<(T::CurrencyId, AssetMetadata<T::Balance>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			GenesisConfig { assets: vec![] }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (currency_id, metadata) in &self.assets {
+				AssetMetadatas::<T>::insert(currency_id, metadata);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::CurrencyId = "CurrencyId", AssetMetadata<T::Balance> = "AssetMetadata")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		AssetRegistered(T::CurrencyId, AssetMetadata<T::Balance>),
+		AssetUpdated(T::CurrencyId, AssetMetadata<T::Balance>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		TokenIdOverflow,
+		AssetNotFound,
+		InvalidSymbol,
+		/// The existential deposit of a token can't change once it has been issued
+		ExistentialDepositLocked,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register a new token, identified by the next free `TokenId`.
+		#[pallet::weight(10_000)]
+		pub fn register_asset(
+			origin: OriginFor<T>,
+			metadata: AssetMetadata<T::Balance>,
+		) -> DispatchResultWithPostInfo {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			Self::ensure_valid(&metadata)?;
+
+			let currency_id = NextTokenId::<T>::try_mutate(|id| -> Result<T::CurrencyId, DispatchError> {
+				let currency_id = T::CurrencyId::from(*id);
+				*id = id.checked_add(1).ok_or(Error::<T>::TokenIdOverflow)?;
+				Ok(currency_id)
+			})?;
+
+			AssetMetadatas::<T>::insert(currency_id, &metadata);
+
+			Self::deposit_event(Event::AssetRegistered(currency_id, metadata));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn update_asset(
+			origin: OriginFor<T>,
+			currency_id: T::CurrencyId,
+			metadata: AssetMetadata<T::Balance>,
+		) -> DispatchResultWithPostInfo {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			Self::ensure_valid(&metadata)?;
+			let existing = Self::asset_metadatas(currency_id).ok_or(Error::<T>::AssetNotFound)?;
+			// raising it would leave balances below the existential deposit
+			ensure!(
+				metadata.existential_deposit == existing.existential_deposit
+					|| T::Currency::total_issuance(currency_id).is_zero(),
+				Error::<T>::ExistentialDepositLocked
+			);
+
+			AssetMetadatas::<T>::insert(currency_id, &metadata);
+
+			Self::deposit_event(Event::AssetUpdated(currency_id, metadata));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn ensure_valid(metadata: &AssetMetadata<T::Balance>) -> Result<(), Error<T>> {
+		if metadata.symbol.is_empty() || metadata.symbol.len() > MAX_SYMBOL_LENGTH {
+			return Err(Error::<T>::InvalidSymbol);
+		}
+		Ok(())
+	}
+
+	/// Existential deposit of a currency, zero if it is not registered.
+	pub fn existential_deposit(currency_id: T::CurrencyId) -> T::Balance {
+		Self::asset_metadatas(currency_id)
+			.map(|metadata| metadata.existential_deposit)
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Minimum order size of a currency, zero if it is not registered.
+	pub fn min_order_size(currency_id: T::CurrencyId) -> T::Balance {
+		Self::asset_metadatas(currency_id)
+			.map(|metadata| metadata.min_order_size)
+			.unwrap_or_else(Zero::zero)
+	}
+}
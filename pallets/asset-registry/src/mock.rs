@@ -0,0 +1,115 @@
+use crate as pallet_asset_registry;
+use frame_support::parameter_types;
+use orml_traits::parameter_type_with_key;
+use frame_support::sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+
+use frame_support::traits::GenesisBuild;
+
+pub type AccountId = u64;
+pub type Balance = u128;
+pub type CurrencyId = u64;
+pub type Amount = i128;
+
+pub const ALICE: AccountId = 1;
+
+pub const DOT: CurrencyId = 1_000;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Module, Call, Config, Storage, Event<T>},
+        AssetRegistry: pallet_asset_registry::{Module, Call, Storage, Event<T>, Config<T>},
+        Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_type_with_key! {
+    pub ExistentialDeposits: |currency_id: CurrencyId| -> Balance {
+        AssetRegistry::existential_deposit(*currency_id)
+    };
+}
+
+impl orml_tokens::Config for Test {
+    type Event = Event;
+    type Balance = Balance;
+    type Amount = Amount;
+    type CurrencyId = CurrencyId;
+    type WeightInfo = ();
+    type ExistentialDeposits = ExistentialDeposits;
+    type OnDust = ();
+}
+
+impl pallet_asset_registry::Config for Test {
+    type Event = Event;
+    type CurrencyId = CurrencyId;
+    type Balance = Balance;
+    type RegisterOrigin = EnsureRoot<AccountId>;
+    type Currency = Tokens;
+}
+
+pub fn dot_metadata() -> crate::AssetMetadata<Balance> {
+    crate::AssetMetadata {
+        symbol: b"DOT".to_vec(),
+        decimals: 10,
+        existential_deposit: 100,
+        min_order_size: 1_000,
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    pallet_asset_registry::GenesisConfig::<Test> {
+        assets: vec![(DOT, dot_metadata())],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
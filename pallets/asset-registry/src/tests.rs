@@ -0,0 +1,94 @@
+use super::*;
+use crate::mock::{Event, *};
+use frame_support::{assert_noop, assert_ok};
+use orml_traits::MultiCurrency;
+
+fn events() -> Vec<Event> {
+    let evt = System::events()
+        .into_iter()
+        .map(|evt| evt.event)
+        .collect::<Vec<_>>();
+    System::reset_events();
+    evt
+}
+
+fn token_metadata() -> AssetMetadata<Balance> {
+    AssetMetadata {
+        symbol: b"TKN".to_vec(),
+        decimals: 12,
+        existential_deposit: 10,
+        min_order_size: 50,
+    }
+}
+
+#[test]
+fn test_register_asset() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AssetRegistry::register_asset(Origin::signed(ALICE), token_metadata()),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            AssetRegistry::register_asset(
+                Origin::root(),
+                AssetMetadata {
+                    symbol: vec![],
+                    ..token_metadata()
+                }
+            ),
+            Error::<Test>::InvalidSymbol
+        );
+
+        assert_ok!(AssetRegistry::register_asset(Origin::root(), token_metadata()));
+        assert_ok!(AssetRegistry::register_asset(Origin::root(), token_metadata()));
+
+        assert_eq!(AssetRegistry::next_token_id(), 2);
+        assert_eq!(AssetRegistry::asset_metadatas(0), Some(token_metadata()));
+        assert_eq!(AssetRegistry::existential_deposit(1), 10);
+        assert_eq!(AssetRegistry::min_order_size(1), 50);
+        assert_eq!(AssetRegistry::existential_deposit(2), 0);
+
+        assert_eq!(
+            events().as_slice(),
+            [
+                Event::pallet_asset_registry(crate::Event::AssetRegistered(0, token_metadata())),
+                Event::pallet_asset_registry(crate::Event::AssetRegistered(1, token_metadata())),
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_update_asset() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(AssetRegistry::existential_deposit(DOT), 100);
+        assert_eq!(AssetRegistry::min_order_size(DOT), 1_000);
+
+        assert_noop!(
+            AssetRegistry::update_asset(Origin::root(), 0, token_metadata()),
+            Error::<Test>::AssetNotFound
+        );
+
+        let metadata = AssetMetadata {
+            min_order_size: 10,
+            ..dot_metadata()
+        };
+        assert_ok!(AssetRegistry::update_asset(Origin::root(), DOT, metadata.clone()));
+        assert_eq!(AssetRegistry::asset_metadatas(DOT), Some(metadata.clone()));
+        assert_eq!(AssetRegistry::min_order_size(DOT), 10);
+
+        //the existential deposit is fixed once the token is issued
+        let raised = AssetMetadata {
+            existential_deposit: 1_000,
+            ..metadata.clone()
+        };
+        assert_ok!(Tokens::deposit(DOT, &ALICE, 500));
+        assert_noop!(
+            AssetRegistry::update_asset(Origin::root(), DOT, raised.clone()),
+            Error::<Test>::ExistentialDepositLocked
+        );
+        assert_ok!(Tokens::withdraw(DOT, &ALICE, 500));
+        assert_ok!(AssetRegistry::update_asset(Origin::root(), DOT, raised));
+        assert_eq!(AssetRegistry::existential_deposit(DOT), 1_000);
+    });
+}
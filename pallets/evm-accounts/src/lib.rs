@@ -0,0 +1,138 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Linking Ethereum-style addresses to accounts.
+//!
+//! An account claims an H160 address by submitting an ECDSA signature, made with the key of that
+//! address, of `Prefix` followed by the hex encoded `AccountId` and the hex of the genesis hash, so
+//! a claim can't be replayed on another chain. The signature is over an Ethereum `personal_sign`
+//! message, so it can be produced by EVM wallets. Each address and each account can only be linked
+//! once, after which `account_of` resolves `MultiAddress::Address20` lookups.
+
+use codec::Encode;
+use frame_support::{sp_runtime::traits::Zero, traits::Get};
+use sp_core::{ecdsa, H160};
+use sp_io::{crypto::secp256k1_ecdsa_recover, hashing::keccak_256};
+use sp_std::vec::Vec;
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Signature of an Ethereum signed message, with its recovery id.
+pub type EcdsaSignature = ecdsa::Signature;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Text prepended to the account id in the signed message
+		#[pallet::constant]
+		type Prefix: Get<&'static [u8]>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	#[pallet::getter(fn account_of)]
+	pub type Accounts<T: Config> = StorageMap<_, Blake2_128Concat, H160, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn address_of)]
+	pub type Addresses<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, H160>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// An address was linked to an account. \[who, address\]
+		AddressClaimed(T::AccountId, H160),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The signature can't be recovered
+		BadSignature,
+		/// The signature wasn't made by the claimed address
+		InvalidSignature,
+		AddressAlreadyClaimed,
+		AccountAlreadyLinked,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Link `address` to the caller, proven by `signature` of the caller's account id and the
+		/// genesis hash.
+		#[pallet::weight(10_000)]
+		pub fn claim_address(
+			origin: OriginFor<T>,
+			address: H160,
+			signature: EcdsaSignature,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!Addresses::<T>::contains_key(&who), Error::<T>::AccountAlreadyLinked);
+			ensure!(!Accounts::<T>::contains_key(address), Error::<T>::AddressAlreadyClaimed);
+
+			let genesis_hash = frame_system::Module::<T>::block_hash(T::BlockNumber::zero());
+			let signer = Self::eth_recover(&signature, &who.encode(), genesis_hash.as_ref())
+				.ok_or(Error::<T>::BadSignature)?;
+			ensure!(signer == address, Error::<T>::InvalidSignature);
+
+			Accounts::<T>::insert(address, &who);
+			Addresses::<T>::insert(&who, address);
+
+			Self::deposit_event(Event::AddressClaimed(who, address));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Ethereum `personal_sign` message of `Prefix` followed by the hex of `what` and of `extra`.
+	pub fn ethereum_signable_message(what: &[u8], extra: &[u8]) -> Vec<u8> {
+		let prefix = T::Prefix::get();
+		let mut length = prefix.len() + (what.len() + extra.len()) * 2;
+		let mut digits = Vec::new();
+		while length > 0 {
+			digits.push(b'0' + (length % 10) as u8);
+			length /= 10;
+		}
+
+		let mut message = b"\x19Ethereum Signed Message:\n".to_vec();
+		message.extend(digits.into_iter().rev());
+		message.extend_from_slice(prefix);
+		message.extend(to_ascii_hex(what));
+		message.extend(to_ascii_hex(extra));
+		message
+	}
+
+	/// Address that signed the Ethereum message of `what` and `extra`.
+	pub fn eth_recover(signature: &EcdsaSignature, what: &[u8], extra: &[u8]) -> Option<H160> {
+		let message = keccak_256(&Self::ethereum_signable_message(what, extra));
+		let public = secp256k1_ecdsa_recover(&signature.0, &message).ok()?;
+		Some(H160::from_slice(&keccak_256(&public[..])[12..]))
+	}
+}
+
+fn to_ascii_hex(data: &[u8]) -> Vec<u8> {
+	let mut hex = Vec::with_capacity(data.len() * 2);
+	let mut push_nibble = |n| hex.push(if n < 10 { b'0' + n } else { b'a' - 10 + n });
+	for &b in data.iter() {
+		push_nibble(b / 16);
+		push_nibble(b % 16);
+	}
+	hex
+}
@@ -0,0 +1,108 @@
+use crate as pallet_evm_accounts;
+use codec::Encode;
+use frame_support::parameter_types;
+use frame_support::sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+use frame_system as system;
+use sp_core::{ecdsa, H160, H256};
+use sp_io::hashing::keccak_256;
+
+pub type AccountId = u64;
+pub type BlockNumber = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Module, Call, Config, Storage, Event<T>},
+        EvmAccounts: pallet_evm_accounts::{Module, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_types! {
+    pub Prefix: &'static [u8] = b"Link to account:";
+}
+
+impl pallet_evm_accounts::Config for Test {
+    type Event = Event;
+    type Prefix = Prefix;
+}
+
+pub fn secret(seed: &[u8]) -> secp256k1::SecretKey {
+    secp256k1::SecretKey::parse(&keccak_256(seed)).unwrap()
+}
+
+pub fn eth_address(secret: &secp256k1::SecretKey) -> H160 {
+    let public = secp256k1::PublicKey::from_secret_key(secret).serialize();
+    H160::from_slice(&keccak_256(&public[1..])[12..])
+}
+
+pub fn eth_sign(secret: &secp256k1::SecretKey, who: AccountId) -> ecdsa::Signature {
+    eth_sign_for_genesis(secret, who, System::block_hash(0))
+}
+
+pub fn eth_sign_for_genesis(
+    secret: &secp256k1::SecretKey,
+    who: AccountId,
+    genesis_hash: H256,
+) -> ecdsa::Signature {
+    let message = keccak_256(&EvmAccounts::ethereum_signable_message(
+        &who.encode(),
+        genesis_hash.as_ref(),
+    ));
+    let (signature, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&message), secret);
+    let mut bytes = [0u8; 65];
+    bytes[..64].copy_from_slice(&signature.serialize());
+    bytes[64] = recovery_id.serialize();
+    ecdsa::Signature::from_raw(bytes)
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
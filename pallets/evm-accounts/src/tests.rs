@@ -0,0 +1,99 @@
+use super::*;
+use crate::mock::{Event, *};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+
+fn events() -> Vec<Event> {
+    let evt = System::events()
+        .into_iter()
+        .map(|evt| evt.event)
+        .collect::<Vec<_>>();
+    System::reset_events();
+    evt
+}
+
+#[test]
+fn test_claim_address() {
+    new_test_ext().execute_with(|| {
+        let alice = secret(b"Alice");
+        let address = eth_address(&alice);
+
+        assert_noop!(
+            EvmAccounts::claim_address(Origin::signed(BOB), address, eth_sign(&alice, ALICE)),
+            Error::<Test>::InvalidSignature
+        );
+
+        assert_ok!(EvmAccounts::claim_address(
+            Origin::signed(ALICE),
+            address,
+            eth_sign(&alice, ALICE)
+        ));
+        assert_eq!(EvmAccounts::account_of(address), Some(ALICE));
+        assert_eq!(EvmAccounts::address_of(ALICE), Some(address));
+        assert_eq!(
+            events(),
+            vec![Event::pallet_evm_accounts(crate::Event::AddressClaimed(
+                ALICE, address
+            ))]
+        );
+    });
+}
+
+#[test]
+fn test_claim_twice() {
+    new_test_ext().execute_with(|| {
+        let alice = secret(b"Alice");
+        let bob = secret(b"Bob");
+
+        assert_ok!(EvmAccounts::claim_address(
+            Origin::signed(ALICE),
+            eth_address(&alice),
+            eth_sign(&alice, ALICE)
+        ));
+
+        assert_noop!(
+            EvmAccounts::claim_address(Origin::signed(ALICE), eth_address(&bob), eth_sign(&bob, ALICE)),
+            Error::<Test>::AccountAlreadyLinked
+        );
+        assert_noop!(
+            EvmAccounts::claim_address(Origin::signed(BOB), eth_address(&alice), eth_sign(&alice, BOB)),
+            Error::<Test>::AddressAlreadyClaimed
+        );
+
+        assert_ok!(EvmAccounts::claim_address(
+            Origin::signed(BOB),
+            eth_address(&bob),
+            eth_sign(&bob, BOB)
+        ));
+        assert_eq!(EvmAccounts::account_of(eth_address(&bob)), Some(BOB));
+    });
+}
+
+#[test]
+fn test_bad_signature() {
+    new_test_ext().execute_with(|| {
+        let address = eth_address(&secret(b"Alice"));
+        let signature = ecdsa::Signature::from_raw([0xff; 65]);
+
+        assert_noop!(
+            EvmAccounts::claim_address(Origin::signed(ALICE), address, signature),
+            Error::<Test>::BadSignature
+        );
+    });
+}
+
+#[test]
+fn test_claim_on_another_chain() {
+    new_test_ext().execute_with(|| {
+        let alice = secret(b"Alice");
+
+        assert_noop!(
+            EvmAccounts::claim_address(
+                Origin::signed(ALICE),
+                eth_address(&alice),
+                eth_sign_for_genesis(&alice, ALICE, H256::repeat_byte(1))
+            ),
+            Error::<Test>::InvalidSignature
+        );
+    });
+}
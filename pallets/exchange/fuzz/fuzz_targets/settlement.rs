@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pallet_exchange::harness::{self, Input};
+
+fuzz_target!(|input: Input| {
+    harness::run(&input);
+});
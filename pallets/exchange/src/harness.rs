@@ -0,0 +1,269 @@
+//! Randomized settlement harness shared by the property tests and the fuzz target.
+//!
+//! `run` sets the fees of the `Input`, applies its sequence of `Action`s to the mock runtime,
+//! ignoring failed calls, and panics as soon as a step breaks one of the settlement invariants:
+//! - the total issuance of every currency is unchanged,
+//! - the balances of the accounts and the fee collector add up to the total issuance,
+//! - the reserved balance of every account is exactly the base amount of its open orders.
+
+use crate::mock::*;
+use crate::{Order, SignedOrder};
+use frame_support::sp_runtime::{testing::TestSignature, Permill};
+use frame_support::traits::OnInitialize;
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+
+pub const ACCOUNTS: [AccountId; 3] = [ALICE, BOB, CHARLIE];
+pub const CURRENCIES: [CurrencyId; 3] = [DOT, KSM, BTC];
+
+/// Balance of every account in every currency before the first action.
+pub const ENDOWMENT: Balance = 1_000_000;
+
+/// Fee rates set at genesis, in parts per million, and the actions applied after it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Input {
+    pub maker_fee: u16,
+    pub taker_fee: u16,
+    pub actions: Vec<Action>,
+}
+
+/// A call of an account of `ACCOUNTS`, or of governance, or the passing of blocks. Indices wrap
+/// around `ACCOUNTS` and `CURRENCIES`, and order ids around the next order id, so any value
+/// selects an existing account, currency or order.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum Action {
+    Submit {
+        who: u8,
+        base: u8,
+        base_amount: u16,
+        target: u8,
+        target_amount: u16,
+    },
+    Take {
+        who: u8,
+        order_id: u8,
+    },
+    Cancel {
+        who: u8,
+        order_id: u8,
+    },
+    /// Take an order signed by `owner`, or by the taker if `forged`.
+    TakeSigned {
+        who: u8,
+        owner: u8,
+        base: u8,
+        base_amount: u16,
+        target: u8,
+        target_amount: u16,
+        nonce: u8,
+        expiry: u8,
+        forged: bool,
+    },
+    CancelSigned {
+        who: u8,
+        nonce: u8,
+        expiry: u8,
+    },
+    /// Match two orders, as an off-chain worker could submit.
+    Match {
+        order_id_a: u8,
+        order_id_b: u8,
+    },
+    /// Match the orders found by the off-chain worker.
+    MatchFound,
+    SetReferrer {
+        who: u8,
+        referrer: u8,
+    },
+    /// Set the referral share, in parts per million.
+    SetReferralShare {
+        share: u32,
+    },
+    /// Lift a halt and reset the reference price of a pair.
+    UnpausePair {
+        base: u8,
+        target: u8,
+    },
+    /// Move `blocks` blocks forward, letting signed orders expire and halts end.
+    Advance {
+        blocks: u8,
+    },
+}
+
+fn account(index: u8) -> AccountId {
+    ACCOUNTS[index as usize % ACCOUNTS.len()]
+}
+
+fn currency(index: u8) -> CurrencyId {
+    CURRENCIES[index as usize % CURRENCIES.len()]
+}
+
+fn order_id(index: u8) -> u32 {
+    let next = ExchangeModule::next_orderid().unwrap_or_default();
+    if next == 0 {
+        0
+    } else {
+        index as u32 % next
+    }
+}
+
+fn apply(action: &Action) {
+    // failed calls are part of the input, only their effects are checked
+    let _ = match *action {
+        Action::Submit {
+            who,
+            base,
+            base_amount,
+            target,
+            target_amount,
+        } => ExchangeModule::submit_order(
+            Origin::signed(account(who)),
+            currency(base),
+            base_amount.into(),
+            currency(target),
+            target_amount.into(),
+        ),
+        Action::Take { who, order_id: id } => {
+            ExchangeModule::take_order(Origin::signed(account(who)), order_id(id))
+        }
+        Action::Cancel { who, order_id: id } => {
+            ExchangeModule::cancel_order(Origin::signed(account(who)), order_id(id))
+        }
+        Action::TakeSigned {
+            who,
+            owner,
+            base,
+            base_amount,
+            target,
+            target_amount,
+            nonce,
+            expiry,
+            forged,
+        } => {
+            let signed_order = SignedOrder {
+                order: Order {
+                    base_currency_id: currency(base),
+                    base_amount: base_amount.into(),
+                    target_currency_id: currency(target),
+                    target_amount: target_amount.into(),
+                    owner: account(owner),
+                    created_at: System::block_number(),
+                },
+                nonce: nonce.into(),
+                expiry: expiry.into(),
+            };
+            let signer = if forged { account(who) } else { account(owner) };
+            let signature =
+                TestSignature(signer, ExchangeModule::signed_order_payload(&signed_order));
+            ExchangeModule::take_signed_order(
+                Origin::signed(account(who)),
+                signed_order,
+                signature,
+            )
+        }
+        Action::CancelSigned { who, nonce, expiry } => ExchangeModule::cancel_signed_order(
+            Origin::signed(account(who)),
+            nonce.into(),
+            expiry.into(),
+        ),
+        Action::Match {
+            order_id_a,
+            order_id_b,
+        } => ExchangeModule::match_orders(
+            Origin::none(),
+            vec![(order_id(order_id_a), order_id(order_id_b))],
+        ),
+        Action::MatchFound => {
+            ExchangeModule::match_orders(Origin::none(), ExchangeModule::find_matches())
+        }
+        Action::SetReferrer { who, referrer } => {
+            ExchangeModule::set_referrer(Origin::signed(account(who)), account(referrer))
+        }
+        Action::SetReferralShare { share } => {
+            ExchangeModule::set_referral_share(Origin::root(), Permill::from_parts(share))
+        }
+        Action::UnpausePair { base, target } => {
+            ExchangeModule::unpause_pair(Origin::root(), currency(base), currency(target))
+        }
+        Action::Advance { blocks } => {
+            for _ in 0..blocks {
+                System::set_block_number(System::block_number() + 1);
+                ExchangeModule::on_initialize(System::block_number());
+            }
+            Ok(().into())
+        }
+    };
+}
+
+fn check(issuance: &[Balance], step: usize, action: Option<&Action>) {
+    for (&currency_id, &issued) in CURRENCIES.iter().zip(issuance) {
+        assert_eq!(
+            Tokens::total_issuance(currency_id),
+            issued,
+            "issuance of {} changed at step {}: {:?}",
+            currency_id,
+            step,
+            action
+        );
+
+        let held = ACCOUNTS
+            .iter()
+            .chain(Some(&FeeCollector::get()))
+            .map(|who| Tokens::total_balance(currency_id, who))
+            .sum::<Balance>();
+        assert_eq!(
+            held, issued,
+            "balances of {} don't add up to its issuance at step {}: {:?}",
+            currency_id, step, action
+        );
+
+        for who in ACCOUNTS.iter() {
+            let open = crate::Orders::<Test>::iter()
+                .map(|(_, order)| order)
+                .filter(|order| order.owner == *who && order.base_currency_id == currency_id)
+                .map(|order| order.base_amount)
+                .sum::<Balance>();
+            assert_eq!(
+                Tokens::reserved_balance(currency_id, who),
+                open,
+                "reserved {} of {} differs from its open orders at step {}: {:?}",
+                currency_id,
+                who,
+                step,
+                action
+            );
+        }
+    }
+
+    if let Err(e) = ExchangeModule::try_state() {
+        panic!("{} at step {}: {:?}", e, step, action);
+    }
+}
+
+/// Apply the actions of `input` to an empty book, with the fees of `input`, every account
+/// holding `ENDOWMENT` of every currency, checking the invariants after every step.
+pub fn run(input: &Input) {
+    let mut builder = ExtBuilder::default().fees(
+        Permill::from_parts(input.maker_fee.into()),
+        Permill::from_parts(input.taker_fee.into()),
+    );
+    for &who in ACCOUNTS.iter() {
+        for &currency_id in CURRENCIES.iter() {
+            builder = builder.balance(who, currency_id, ENDOWMENT);
+        }
+    }
+
+    builder.build().execute_with(|| {
+        let issuance = CURRENCIES
+            .iter()
+            .map(|&currency_id| Tokens::total_issuance(currency_id))
+            .collect::<Vec<_>>();
+        check(&issuance, 0, None);
+
+        for (step, action) in input.actions.iter().enumerate() {
+            apply(action);
+            check(&issuance, step + 1, Some(action));
+        }
+    });
+}
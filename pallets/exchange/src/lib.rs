@@ -2,28 +2,43 @@
 
 use codec::{Decode, Encode};
 use frame_support::sp_runtime::{
-	traits::{AtLeast32BitUnsigned, Bounded, CheckedAdd, MaybeSerializeDeserialize, One, Zero},
-	RuntimeDebug,
+	traits::{
+		AtLeast32BitUnsigned, Bounded, CheckedAdd, CheckedSub, IdentifyAccount, MaybeSerializeDeserialize, One,
+		Saturating, UniqueSaturatedInto, Verify, Zero,
+	},
+	DispatchError, DispatchResult, FixedPointNumber, FixedU128, Permill, PerThing, RuntimeDebug,
 };
-use frame_support::{traits::BalanceStatus, Parameter, transactional};
-use frame_system::ensure_signed;
-use orml_traits::{MultiCurrency, MultiReservableCurrency};
+use frame_support::{debug, ensure, traits::{BalanceStatus, Get}, Parameter, transactional};
+use frame_system::{ensure_signed, offchain::{SendTransactionTypes, SubmitTransaction}};
+use orml_traits::{GetByKey, MultiCurrency, MultiReservableCurrency};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::vec::Vec;
 /// Edit this file to define custom logic or remove it if it is not needed.
 /// Learn more about FRAME and the core library of Substrate FRAME pallets:
 /// <https://substrate.dev/docs/en/knowledgebase/runtime/frame>
 pub use pallet::*;
 
-#[cfg(test)]
-mod mock;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod harness;
+
+#[cfg(test)]
+mod proptests;
+
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
+
 #[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
-pub struct Order<CurrencyId, Balance, AccountId> {
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Order<CurrencyId, Balance, AccountId, BlockNumber> {
 	pub base_currency_id: CurrencyId,
 	#[codec(compact)]
 	pub base_amount: Balance,
@@ -31,13 +46,208 @@ pub struct Order<CurrencyId, Balance, AccountId> {
 	#[codec(compact)]
 	pub target_amount: Balance,
 	pub owner: AccountId,
+	/// Block the order was submitted in, or signed for if it is a signed order
+	pub created_at: BlockNumber,
+}
+
+/// Layout version of the pallet's storage, see `migrations`.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Releases {
+	/// Storage of chains started before storage was versioned
+	V0,
+	/// `Order::created_at` added
+	V1,
+	/// Only listed pairs can be traded, the pairs of the open orders were listed on upgrade
+	V2,
+	/// Delegates' notional limits are per base currency
+	V3,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V0
+	}
+}
+
+/// Order signed off-chain by its owner, to be filled by whoever submits it with
+/// `take_signed_order`. The owner's funds are not reserved until then.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SignedOrder<CurrencyId, Balance, AccountId, BlockNumber> {
+	pub order: Order<CurrencyId, Balance, AccountId, BlockNumber>,
+	/// Chosen by the owner, each nonce can be used by only one order until it expires
+	#[codec(compact)]
+	pub nonce: u64,
+	/// Last block the order can be taken in
+	pub expiry: BlockNumber,
+}
+
+/// Prepended to the genesis hash and the SCALE encoded `SignedOrder` to form the message signed
+/// by its owner.
+pub const SIGNED_ORDER_CONTEXT: &[u8] = b"orml-exchange/signed-order:";
+
+/// Settlement of an order, reported by `Event::OrderFilled`. The maker is the owner of the order,
+/// the taker the account filling it. Orders are always filled in full and leave the book.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Fill<AccountId, OrderId, CurrencyId, Balance> {
+	pub order_id: OrderId,
+	pub maker: AccountId,
+	pub taker: AccountId,
+	/// Currency paid by the maker to the taker
+	pub base_currency_id: CurrencyId,
+	/// Currency paid by the taker to the maker
+	pub target_currency_id: CurrencyId,
+	/// Amount of the base currency paid by the maker
+	#[codec(compact)]
+	pub base_amount: Balance,
+	/// Amount of the target currency paid by the taker
+	#[codec(compact)]
+	pub target_amount: Balance,
+	/// Fee paid by the maker, in the target currency
+	#[codec(compact)]
+	pub maker_fee: Balance,
+	/// Fee paid by the taker, in the base currency
+	#[codec(compact)]
+	pub taker_fee: Balance,
+}
+
+/// Fee rates charged on `take_order` fills.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct FeeRate {
+	/// Charged to the order owner, on the target amount they receive
+	pub maker: Permill,
+	/// Charged to the taker, on the base amount they receive
+	pub taker: Permill,
+}
+
+/// Fee discounts granted once an account's rolling volume in a currency reaches `volume`.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq, Default)]
+pub struct FeeTier<Balance> {
+	pub volume: Balance,
+	pub maker_discount: Permill,
+	pub taker_discount: Permill,
+}
+
+// `Permill` doesn't implement `TypeInfo`, the fee types are described by hand.
+#[cfg(feature = "std")]
+impl scale_info::TypeInfo for FeeRate {
+	type Identity = Self;
+
+	fn type_info() -> scale_info::Type {
+		scale_info::Type::builder()
+			.path(scale_info::Path::new("FeeRate", module_path!()))
+			.composite(
+				scale_info::build::Fields::named()
+					.field_of::<u32>("maker", "Permill")
+					.field_of::<u32>("taker", "Permill"),
+			)
+	}
 }
 
+#[cfg(feature = "std")]
+impl<Balance: scale_info::TypeInfo + 'static> scale_info::TypeInfo for FeeTier<Balance> {
+	type Identity = Self;
+
+	fn type_info() -> scale_info::Type {
+		scale_info::Type::builder()
+			.path(scale_info::Path::new("FeeTier", module_path!()))
+			.type_params(scale_info::tuple_meta_type!(Balance))
+			.composite(
+				scale_info::build::Fields::named()
+					.field_of::<Balance>("volume", "Balance")
+					.field_of::<u32>("maker_discount", "Permill")
+					.field_of::<u32>("taker_discount", "Permill"),
+			)
+	}
+}
+
+/// What a delegate may do on behalf of an order owner.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct DelegatePermissions<CurrencyId, Balance> {
+	pub can_submit: bool,
+	pub can_cancel: bool,
+	/// Pairs the delegate may submit orders for, any pair if `None`
+	pub pairs: Option<Vec<(CurrencyId, CurrencyId)>>,
+	/// Largest total `base_amount` of the orders selling each currency the delegate may submit
+	/// until it is authorized again, unlimited if `None`. Currencies without a limit can't be
+	/// sold.
+	pub max_notional: Option<Vec<(CurrencyId, Balance)>>,
+}
+
+/// Maximum number of pairs a delegate can be restricted to.
+pub const MAX_DELEGATE_PAIRS: usize = 16;
+
+/// Maximum number of currencies a delegate can be given a notional limit for.
+pub const MAX_DELEGATE_CURRENCIES: usize = 16;
+
+/// Number of days of trading volume considered for fee tiers.
+pub const VOLUME_WINDOW_DAYS: u32 = 30;
+
+/// Maximum number of fee tiers per currency.
+pub const MAX_FEE_TIERS: usize = 16;
+
+/// Maximum number of matches settled by one `match_orders` call.
+pub const MAX_MATCHES: usize = 16;
+
+/// Number of blocks an unsigned `match_orders` transaction stays valid in the pool.
+pub const MATCH_LONGEVITY: u64 = 5;
+
 type BalanceOf<T> =
 	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
 type CurrencyIdOf<T> =
 	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
-type OrderOf<T> = Order<CurrencyIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::AccountId>;
+type OrderOf<T> = Order<
+	CurrencyIdOf<T>,
+	BalanceOf<T>,
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::BlockNumber,
+>;
+type SignedOrderOf<T> = SignedOrder<
+	CurrencyIdOf<T>,
+	BalanceOf<T>,
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::BlockNumber,
+>;
+type FillOf<T> = Fill<
+	<T as frame_system::Config>::AccountId,
+	<T as Config>::OrderId,
+	CurrencyIdOf<T>,
+	BalanceOf<T>,
+>;
+type DelegatePermissionsOf<T> = DelegatePermissions<CurrencyIdOf<T>, BalanceOf<T>>;
+/// The pair of two currencies, in a canonical order independent of the trade direction.
+pub fn trading_pair<CurrencyId: Encode>(a: CurrencyId, b: CurrencyId) -> (CurrencyId, CurrencyId) {
+	if a.encode() <= b.encode() {
+		(a, b)
+	} else {
+		(b, a)
+	}
+}
+
+/// Unordered pair of currencies, see `Pallet::trading_pair`.
+type PairOf<T> = (CurrencyIdOf<T>, CurrencyIdOf<T>);
+
+/// Handler for when a new order has been placed on the book.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnOrderCreated<OrderId, Order> {
+	fn on_order_created(order_id: &OrderId, order: &Order);
+}
+
+/// Handler for when an order has been filled by a taker.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnOrderFilled<AccountId, OrderId, Order> {
+	fn on_order_filled(taker: &AccountId, order_id: &OrderId, order: &Order);
+}
+
+/// Handler for when an order has been cancelled by its owner.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnOrderCancelled<OrderId, Order> {
+	fn on_order_cancelled(order_id: &OrderId, order: &Order);
+}
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -47,7 +257,7 @@ pub mod pallet {
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 		/// Currency
@@ -59,12 +269,50 @@ pub mod pallet {
 			+ Copy
 			+ MaybeSerializeDeserialize
 			+ Bounded;
+		/// Handler invoked after an order is created
+		type OnOrderCreated: OnOrderCreated<Self::OrderId, OrderOf<Self>>;
+		/// Handler invoked after an order is taken
+		type OnOrderFilled: OnOrderFilled<Self::AccountId, Self::OrderId, OrderOf<Self>>;
+		/// Handler invoked after an order is cancelled
+		type OnOrderCancelled: OnOrderCancelled<Self::OrderId, OrderOf<Self>>;
+		/// Origin allowed to list pairs and change fee parameters
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+		/// Account receiving trading fees
+		type FeeCollector: Get<Self::AccountId>;
+		/// Number of blocks in a day, used to bucket trading volume
+		#[pallet::constant]
+		type BlocksPerDay: Get<Self::BlockNumber>;
+		/// Origin allowed to pause and unpause trading
+		type PauseOrigin: EnsureOrigin<Self::Origin>;
+		/// Maximum deviation of a fill price from the reference price before the pair is halted
+		#[pallet::constant]
+		type CircuitBreakerThreshold: Get<Permill>;
+		/// Number of blocks a pair stays halted after the circuit breaker triggers
+		#[pallet::constant]
+		type CircuitBreakerDuration: Get<Self::BlockNumber>;
+		/// Minimum amount of a currency on either side of an order
+		type MinimumOrderSize: GetByKey<CurrencyIdOf<Self>, BalanceOf<Self>>;
+		/// Minimum amount of a currency on either side of a fill for its price to be checked by,
+		/// and update the reference price of, the circuit breaker
+		type CircuitBreakerMinimumSize: GetByKey<CurrencyIdOf<Self>, BalanceOf<Self>>;
+		/// Signature of orders signed off-chain
+		type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+		/// Key signing orders off-chain, identifying their owner
+		type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+		/// Priority of the unsigned `match_orders` transactions of the off-chain worker
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
 	}
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
 
+	/// Layout version of the storage, bumped by `on_runtime_upgrade` migrations.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_version)]
+	pub type StorageVersion<T: Config> = StorageValue<_, Releases, ValueQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn orders)]
 	pub type Orders<T: Config> = StorageMap<_, Twox64Concat, T::OrderId, OrderOf<T>>;
@@ -73,15 +321,210 @@ pub mod pallet {
 	#[pallet::getter(fn next_orderid)]
 	pub type NextOrderId<T: Config> = StorageValue<_, T::OrderId>;
 
+	/// Nonces of signed orders that were taken or cancelled, per owner.
+	#[pallet::storage]
+	#[pallet::getter(fn used_nonces)]
+	pub type UsedNonces<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::AccountId, Twox64Concat, u64, bool, ValueQuery>;
+
+	/// `UsedNonces` by the expiry of their order, removed from both once it has passed.
+	#[pallet::storage]
+	pub type NonceExpiries<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Twox64Concat,
+		(T::AccountId, u64),
+		(),
+	>;
+
+	/// Permissions granted by an owner to a delegate trading on their behalf.
+	#[pallet::storage]
+	#[pallet::getter(fn delegates)]
+	pub type Delegates<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		DelegatePermissionsOf<T>,
+	>;
+
+	/// Total `base_amount` per base currency of the orders a delegate submitted for an owner
+	/// since it was authorized, counted against `DelegatePermissions::max_notional`.
+	#[pallet::storage]
+	#[pallet::getter(fn delegated_notional)]
+	pub type DelegatedNotional<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		Vec<(CurrencyIdOf<T>, BalanceOf<T>)>,
+		ValueQuery,
+	>;
+
+	/// Delegate that submitted an open order. It can neither take the order nor have it matched
+	/// with its own orders.
+	#[pallet::storage]
+	#[pallet::getter(fn delegated_orders)]
+	pub type DelegatedOrders<T: Config> = StorageMap<_, Twox64Concat, T::OrderId, T::AccountId>;
+
+	/// Pairs orders can be submitted and taken for.
+	#[pallet::storage]
+	#[pallet::getter(fn listed_pairs)]
+	pub type ListedPairs<T: Config> = StorageMap<_, Twox64Concat, PairOf<T>, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn trading_paused)]
+	pub type TradingPaused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn paused_pairs)]
+	pub type PausedPairs<T: Config> = StorageMap<_, Twox64Concat, PairOf<T>, bool, ValueQuery>;
+
+	/// Block until which a pair is halted by the circuit breaker.
+	#[pallet::storage]
+	#[pallet::getter(fn halted_until)]
+	pub type HaltedUntil<T: Config> = StorageMap<_, Twox64Concat, PairOf<T>, T::BlockNumber>;
+
+	/// Last accepted fill price of a pair, in units of `pair.1` per unit of `pair.0`.
+	#[pallet::storage]
+	#[pallet::getter(fn reference_prices)]
+	pub type ReferencePrices<T: Config> = StorageMap<_, Twox64Concat, PairOf<T>, FixedU128>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn fee_rate)]
+	pub type FeeRates<T: Config> = StorageValue<_, FeeRate, ValueQuery>;
+
+	/// Fee tiers of a currency, sorted by ascending volume.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_tiers)]
+	pub type FeeTiers<T: Config> =
+		StorageMap<_, Twox64Concat, CurrencyIdOf<T>, Vec<FeeTier<BalanceOf<T>>>, ValueQuery>;
+
+	/// Traded volume of an account per currency, bucketed by day and covering at most
+	/// `VOLUME_WINDOW_DAYS` days.
+	#[pallet::storage]
+	#[pallet::getter(fn trading_volumes)]
+	pub type TradingVolumes<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		Twox64Concat,
+		CurrencyIdOf<T>,
+		Vec<(T::BlockNumber, BalanceOf<T>)>,
+		ValueQuery,
+	>;
+
+	/// Share of the fees paid by a referred account that goes to the referrer.
+	#[pallet::storage]
+	#[pallet::getter(fn referral_share)]
+	pub type ReferralShare<T: Config> = StorageValue<_, Permill, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn referrers)]
+	pub type Referrers<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, T::AccountId>;
+
+	/// Total referral rewards ever paid to a referrer, per currency.
+	#[pallet::storage]
+	#[pallet::getter(fn referral_earnings)]
+	pub type ReferralEarnings<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		Twox64Concat,
+		CurrencyIdOf<T>,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub listed_pairs: Vec<(CurrencyIdOf<T>, CurrencyIdOf<T>)>,
+		pub fee_rate: FeeRate,
+		/// Orders placed on the book at genesis, as
+		/// `(owner, base_currency_id, base_amount, target_currency_id, target_amount)`.
+		/// The base amount is reserved from the owner, who must be endowed by then.
+		pub orders: Vec<(T::AccountId, CurrencyIdOf<T>, BalanceOf<T>, CurrencyIdOf<T>, BalanceOf<T>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			GenesisConfig {
+				listed_pairs: vec![],
+				fee_rate: Default::default(),
+				orders: vec![],
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			StorageVersion::<T>::put(migrations::LATEST);
+			for (currency_id_a, currency_id_b) in &self.listed_pairs {
+				assert!(currency_id_a != currency_id_b, "a pair needs two different currencies");
+				ListedPairs::<T>::insert(Pallet::<T>::trading_pair(*currency_id_a, *currency_id_b), true);
+			}
+			FeeRates::<T>::put(self.fee_rate);
+			for (owner, base_currency_id, base_amount, target_currency_id, target_amount) in &self.orders {
+				Pallet::<T>::do_submit_order(
+					owner.clone(),
+					*base_currency_id,
+					*base_amount,
+					*target_currency_id,
+					*target_amount,
+				)
+				.expect("genesis orders must be for listed pairs and funded");
+			}
+		}
+	}
+
 	// Pallets use events to inform users when important changes are made.
 	// https://substrate.dev/docs/en/knowledgebase/runtime/events
 	#[pallet::event]
-	#[pallet::metadata(T::AccountId = "AccountId", T::OrderId = "OrderId", OrderOf<T> = "Order", BalanceOf<T> = "Balance")]
+	#[pallet::metadata(T::AccountId = "AccountId", T::OrderId = "OrderId", OrderOf<T> = "Order", BalanceOf<T> = "Balance", CurrencyIdOf<T> = "CurrencyId", T::BlockNumber = "BlockNumber", FillOf<T> = "Fill")]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
 		OrderCreated(T::OrderId, OrderOf<T>),
 		OrderTaken(T::AccountId, T::OrderId, OrderOf<T>),
 		OrderCancelled(T::OrderId),
+		/// A signed order was filled under a new order id. \[taker, order_id, nonce, order\]
+		SignedOrderTaken(T::AccountId, T::OrderId, u64, OrderOf<T>),
+		/// \[owner, nonce\]
+		SignedOrderCancelled(T::AccountId, u64),
+		/// Two crossing orders were filled against each other. \[order_id, order_id\]
+		OrdersMatched(T::OrderId, T::OrderId),
+		FeesUpdated(FeeRate),
+		/// \[currency_id\]
+		FeeTiersUpdated(CurrencyIdOf<T>),
+		ReferralShareUpdated(Permill),
+		/// \[who, referrer\]
+		ReferrerSet(T::AccountId, T::AccountId),
+		/// \[referrer, referee, currency_id, amount\]
+		ReferralRewardPaid(T::AccountId, T::AccountId, CurrencyIdOf<T>, BalanceOf<T>),
+		/// \[owner, delegate\]
+		DelegateAuthorized(T::AccountId, T::AccountId),
+		/// \[owner, delegate\]
+		DelegateRevoked(T::AccountId, T::AccountId),
+		PairListed(CurrencyIdOf<T>, CurrencyIdOf<T>),
+		PairDelisted(CurrencyIdOf<T>, CurrencyIdOf<T>),
+		TradingPaused,
+		TradingUnpaused,
+		PairPaused(CurrencyIdOf<T>, CurrencyIdOf<T>),
+		PairUnpaused(CurrencyIdOf<T>, CurrencyIdOf<T>),
+		/// \[currency_id_0, currency_id_1, price, halted_until\]
+		CircuitBreakerTriggered(CurrencyIdOf<T>, CurrencyIdOf<T>, FixedU128, T::BlockNumber),
+		/// An order was filled, by `take_order`, `take_signed_order` or `match_orders`.
+		/// Deposited before the `OrderTaken`, `SignedOrderTaken` or `OrdersMatched` event of the
+		/// call. Matched orders are each filled by the owner of the other, without taker fee.
+		/// \[fill\]
+		OrderFilled(FillOf<T>),
+		/// Reserved funds of an order were returned to its owner, on cancellation or what remains
+		/// of a matched order. \[order_id, owner, currency_id, amount\]
+		OrderRefunded(T::OrderId, T::AccountId, CurrencyIdOf<T>, BalanceOf<T>),
 	}
 
 	// Errors inform users that something went wrong.
@@ -91,10 +534,91 @@ pub mod pallet {
 		InvalidOrderId,
 		InsufficientBalance,
 		NotOwner,
+		ReferrerAlreadySet,
+		InvalidReferrer,
+		InvalidFeeTiers,
+		InvalidPair,
+		PairNotListed,
+		TradingPaused,
+		PairPaused,
+		PairHalted,
+		NoPermission,
+		InvalidDelegate,
+		OrderTooSmall,
+		WouldLeaveDust,
+		InvalidSignature,
+		OrderExpired,
+		NonceUsed,
+		/// The orders don't exist, don't cross, are matched more than once or one was submitted by
+		/// the owner of the other as a delegate
+		InvalidMatch,
 	}
 
 	#[pallet::hooks]
-	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Forget the nonces of the orders that expired in the previous block, which can't be
+		/// taken anymore.
+		fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+			let mut count: Weight = 0;
+			if let Some(expired) = now.checked_sub(&One::one()) {
+				for ((owner, nonce), ()) in NonceExpiries::<T>::drain_prefix(expired) {
+					UsedNonces::<T>::remove(owner, nonce);
+					count += 1;
+				}
+			}
+			T::DbWeight::get().reads_writes(count + 1, count * 2)
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			migrations::migrate::<T>()
+		}
+
+		/// With the `try-runtime` feature, check the state invariants after every block. Violations
+		/// are logged rather than panicking, so a node running such a build keeps importing blocks.
+		#[cfg(feature = "try-runtime")]
+		fn on_finalize(now: BlockNumberFor<T>) {
+			if let Err(e) = Self::try_state() {
+				debug::error!("exchange: state invariants violated at block {:?}: {}", now, e);
+			}
+		}
+
+		/// Look for crossing orders and submit them to `match_orders`.
+		fn offchain_worker(_now: BlockNumberFor<T>) {
+			let matches = Self::find_matches();
+			if matches.is_empty() {
+				return;
+			}
+			let call = Call::match_orders(matches);
+			if SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).is_err() {
+				debug::warn!("exchange: failed to submit matches");
+			}
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::match_orders(matches) = call {
+				// only the local off-chain worker and block authors may submit matches, as they are
+				// unsigned and could otherwise be used to flood the pool
+				match source {
+					TransactionSource::Local | TransactionSource::InBlock => {}
+					_ => return InvalidTransaction::Call.into(),
+				}
+				Self::check_matches(matches).map_err(|_| InvalidTransaction::Call)?;
+				ValidTransaction::with_tag_prefix("ExchangeMatcher")
+					.priority(T::UnsignedPriority::get())
+					.and_provides(matches)
+					.longevity(MATCH_LONGEVITY)
+					.propagate(false)
+					.build()
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
 	// These functions materialize as "extrinsics", which are often compared to transactions.
@@ -111,30 +635,67 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
-			NextOrderId::<T>::try_mutate(|id| -> DispatchResultWithPostInfo {
-				let order_id = id.unwrap_or_default();
-
-				let order = Order {
-					base_currency_id,
-					base_amount,
-					target_currency_id,
-					target_amount,
-					owner: who.clone(),
-				};
-
-				*id = Some(
-					order_id
-						.checked_add(&One::one())
-						.ok_or(Error::<T>::OrderIdOverflow)?,
-				);
+			Self::do_submit_order(who, base_currency_id, base_amount, target_currency_id, target_amount)?;
+			Ok(().into())
+		}
 
-				T::Currency::reserve(base_currency_id, &who, base_amount)?;
+		/// Submit an order on behalf of `owner`, who must have authorized the caller.
+		/// Funds are reserved from, and paid to, `owner`.
+		#[pallet::weight(10_000)]
+		pub fn submit_order_for(
+			origin: OriginFor<T>,
+			owner: T::AccountId,
+			base_currency_id: CurrencyIdOf<T>,
+			base_amount: BalanceOf<T>,
+			target_currency_id: CurrencyIdOf<T>,
+			target_amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
 
-				Orders::<T>::insert(order_id, &order);
+			let permissions = Self::delegates(&owner, &who).ok_or(Error::<T>::NoPermission)?;
+			ensure!(permissions.can_submit, Error::<T>::NoPermission);
+			if let Some(pairs) = permissions.pairs {
+				let pair = Self::trading_pair(base_currency_id, target_currency_id);
+				ensure!(pairs.contains(&pair), Error::<T>::NoPermission);
+			}
+			// amounts of different currencies can't be added up, each is limited on its own
+			let notional = match permissions.max_notional {
+				Some(limits) => {
+					let (_, max_notional) = limits
+						.into_iter()
+						.find(|(currency_id, _)| *currency_id == base_currency_id)
+						.ok_or(Error::<T>::NoPermission)?;
+					let mut notional = Self::delegated_notional(&owner, &who);
+					let total = match notional
+						.iter_mut()
+						.find(|(currency_id, _)| *currency_id == base_currency_id)
+					{
+						Some((_, total)) => {
+							*total = total.saturating_add(base_amount);
+							*total
+						}
+						None => {
+							notional.push((base_currency_id, base_amount));
+							base_amount
+						}
+					};
+					ensure!(total <= max_notional, Error::<T>::NoPermission);
+					Some(notional)
+				}
+				None => None,
+			};
 
-				Self::deposit_event(Event::OrderCreated(order_id, order));
-				Ok(().into())
-			})?;
+			let order_id = Self::do_submit_order(
+				owner.clone(),
+				base_currency_id,
+				base_amount,
+				target_currency_id,
+				target_amount,
+			)?;
+			if let Some(notional) = notional {
+				DelegatedNotional::<T>::insert(&owner, &who, notional);
+			}
+			DelegatedOrders::<T>::insert(order_id, &who);
 			Ok(().into())
 		}
 
@@ -146,30 +707,133 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
-			Orders::<T>::try_mutate_exists(order_id, |order| -> DispatchResultWithPostInfo {
+			let order = Self::orders(order_id).ok_or(Error::<T>::InvalidOrderId)?;
+			// a delegate could otherwise buy the owner's funds at a price of its choosing
+			ensure!(
+				Self::delegated_orders(order_id).as_ref() != Some(&who),
+				Error::<T>::NoPermission
+			);
+			let pair = Self::trading_pair(order.base_currency_id, order.target_currency_id);
+			Self::ensure_trading_allowed(&pair)?;
+			// the halt must persist, so the call succeeds without filling the order
+			if !Self::check_circuit_breaker(&pair, &order, &who) {
+				return Ok(().into());
+			}
+
+			Orders::<T>::try_mutate_exists(order_id, |order| -> DispatchResult {
 				let order = order.take().ok_or(Error::<T>::InvalidOrderId)?;
 
-				T::Currency::transfer(
-					order.target_currency_id,
-					&who,
-					&order.owner,
-					order.target_amount,
-				)?;
-				let val = T::Currency::repatriate_reserved(
-					order.base_currency_id,
-					&order.owner,
-					&who,
-					order.base_amount,
-					BalanceStatus::Free,
-				)?;
-				ensure!(val.is_zero(), Error::<T>::InsufficientBalance);
+				Self::do_fill(&who, order_id, &order, true)?;
+				DelegatedOrders::<T>::remove(order_id);
 
 				Self::deposit_event(Event::OrderTaken(who, order_id, order));
-				Ok(().into())
+				Ok(())
 			})?;
 			Ok(().into())
 		}
 
+		/// Take an order signed off-chain by its owner, who doesn't submit any transaction.
+		/// The caller pays the transaction fee and the `target_amount` of the order.
+		#[pallet::weight(10_000)]
+		#[transactional]
+		pub fn take_signed_order(
+			origin: OriginFor<T>,
+			signed_order: SignedOrderOf<T>,
+			signature: T::OffchainSignature,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let SignedOrder { order, nonce, expiry } = signed_order.clone();
+			ensure!(
+				signature.verify(&Self::signed_order_payload(&signed_order)[..], &order.owner),
+				Error::<T>::InvalidSignature
+			);
+			ensure!(
+				frame_system::Module::<T>::block_number() <= expiry,
+				Error::<T>::OrderExpired
+			);
+			ensure!(!Self::used_nonces(&order.owner, nonce), Error::<T>::NonceUsed);
+			Self::ensure_order_size(&order)?;
+
+			let pair = Self::trading_pair(order.base_currency_id, order.target_currency_id);
+			Self::ensure_trading_allowed(&pair)?;
+			// the halt must persist, so the call succeeds without filling the order
+			if !Self::check_circuit_breaker(&pair, &order, &who) {
+				return Ok(().into());
+			}
+
+			Self::use_nonce(&order.owner, nonce, expiry);
+			let order_id = NextOrderId::<T>::try_mutate(|id| -> Result<T::OrderId, DispatchError> {
+				let order_id = id.unwrap_or_default();
+				*id = Some(
+					order_id
+						.checked_add(&One::one())
+						.ok_or(Error::<T>::OrderIdOverflow)?,
+				);
+				Ok(order_id)
+			})?;
+
+			Self::do_fill(&who, order_id, &order, false)?;
+
+			Self::deposit_event(Event::SignedOrderTaken(who, order_id, nonce, order));
+			Ok(().into())
+		}
+
+		/// Fill pairs of crossing orders against each other, as found by the off-chain worker.
+		///
+		/// Both orders of a match are filled in full at their own price: each owner receives the
+		/// `target_amount` they asked for and gets back what remains of their `base_amount`. Both
+		/// prices go through the circuit breaker, and a halt stops the remaining matches.
+		#[pallet::weight(10_000 * matches.len() as Weight)]
+		#[transactional]
+		pub fn match_orders(
+			origin: OriginFor<T>,
+			matches: Vec<(T::OrderId, T::OrderId)>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+
+			for (a, b) in Self::check_matches(&matches)? {
+				let (order_id_a, order_id_b) = (a.0, b.0);
+				let pair = Self::trading_pair(a.1.base_currency_id, a.1.target_currency_id);
+				Self::ensure_trading_allowed(&pair)?;
+				// each order is filled at its own price, so either can trip the breaker, and the
+				// halt must persist, so the call succeeds without filling the rest
+				let reference = Self::reference_prices(pair);
+				if !Self::check_circuit_breaker(&pair, &a.1, &b.1.owner)
+					|| !Self::check_circuit_breaker(&pair, &b.1, &a.1.owner)
+				{
+					// a halt leaves the reference as it was before the match
+					ReferencePrices::<T>::mutate(pair, |price| *price = reference);
+					break;
+				}
+
+				Self::do_match(a, b)?;
+
+				Self::deposit_event(Event::OrdersMatched(order_id_a, order_id_b));
+			}
+			Ok(().into())
+		}
+
+		/// Invalidate a signed order of the caller before it is taken. The nonce is used until
+		/// `expiry`, which must not be before the `expiry` of the signed order.
+		#[pallet::weight(10_000)]
+		pub fn cancel_signed_order(
+			origin: OriginFor<T>,
+			nonce: u64,
+			expiry: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!Self::used_nonces(&who, nonce), Error::<T>::NonceUsed);
+			// an order that already expired can't be taken anyway
+			if frame_system::Module::<T>::block_number() <= expiry {
+				Self::use_nonce(&who, nonce, expiry);
+			}
+
+			Self::deposit_event(Event::SignedOrderCancelled(who, nonce));
+			Ok(().into())
+		}
+
 		#[pallet::weight(10_000)]
 		pub fn cancel_order(
 			origin: OriginFor<T>,
@@ -180,12 +844,723 @@ pub mod pallet {
 			Orders::<T>::try_mutate_exists(order_id, |order| -> DispatchResult {
 				let order = order.take().ok_or(Error::<T>::InvalidOrderId)?;
 
-				ensure!(order.owner == who, Error::<T>::NotOwner);
+				ensure!(
+					order.owner == who
+						|| Self::delegates(&order.owner, &who).map_or(false, |p| p.can_cancel),
+					Error::<T>::NotOwner
+				);
+
+				T::Currency::unreserve(order.base_currency_id, &order.owner, order.base_amount);
+				DelegatedOrders::<T>::remove(order_id);
 
+				T::OnOrderCancelled::on_order_cancelled(&order_id, &order);
+				Self::deposit_event(Event::OrderRefunded(
+					order_id,
+					order.owner,
+					order.base_currency_id,
+					order.base_amount,
+				));
 				Self::deposit_event(Event::OrderCancelled(order_id));
 				Ok(())
 			})?;
 			Ok(().into())
 		}
+
+		/// Authorize `delegate` to trade on behalf of the caller, replacing any previous
+		/// permissions of `delegate`.
+		#[pallet::weight(10_000)]
+		pub fn authorize_delegate(
+			origin: OriginFor<T>,
+			delegate: T::AccountId,
+			permissions: DelegatePermissionsOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(delegate != who, Error::<T>::InvalidDelegate);
+			let mut permissions = permissions;
+			if let Some(pairs) = permissions.pairs.as_mut() {
+				ensure!(pairs.len() <= MAX_DELEGATE_PAIRS, Error::<T>::InvalidDelegate);
+				for pair in pairs.iter_mut() {
+					*pair = Self::trading_pair(pair.0, pair.1);
+				}
+			}
+			if let Some(limits) = permissions.max_notional.as_ref() {
+				ensure!(limits.len() <= MAX_DELEGATE_CURRENCIES, Error::<T>::InvalidDelegate);
+			}
+
+			Delegates::<T>::insert(&who, &delegate, permissions);
+			DelegatedNotional::<T>::remove(&who, &delegate);
+
+			Self::deposit_event(Event::DelegateAuthorized(who, delegate));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn revoke_delegate(
+			origin: OriginFor<T>,
+			delegate: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(
+				Delegates::<T>::contains_key(&who, &delegate),
+				Error::<T>::NoPermission
+			);
+			Delegates::<T>::remove(&who, &delegate);
+			DelegatedNotional::<T>::remove(&who, &delegate);
+
+			Self::deposit_event(Event::DelegateRevoked(who, delegate));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn list_pair(
+			origin: OriginFor<T>,
+			currency_id_a: CurrencyIdOf<T>,
+			currency_id_b: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(currency_id_a != currency_id_b, Error::<T>::InvalidPair);
+			let pair = Self::trading_pair(currency_id_a, currency_id_b);
+			ListedPairs::<T>::insert(pair, true);
+
+			Self::deposit_event(Event::PairListed(pair.0, pair.1));
+			Ok(().into())
+		}
+
+		/// Stop new orders and fills of a pair. Open orders can still be cancelled.
+		#[pallet::weight(10_000)]
+		pub fn delist_pair(
+			origin: OriginFor<T>,
+			currency_id_a: CurrencyIdOf<T>,
+			currency_id_b: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let pair = Self::trading_pair(currency_id_a, currency_id_b);
+			ensure!(Self::listed_pairs(pair), Error::<T>::PairNotListed);
+			ListedPairs::<T>::remove(pair);
+
+			Self::deposit_event(Event::PairDelisted(pair.0, pair.1));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn pause(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			TradingPaused::<T>::put(true);
+
+			Self::deposit_event(Event::TradingPaused);
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn unpause(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			TradingPaused::<T>::kill();
+
+			Self::deposit_event(Event::TradingUnpaused);
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn pause_pair(
+			origin: OriginFor<T>,
+			currency_id_a: CurrencyIdOf<T>,
+			currency_id_b: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			let pair = Self::trading_pair(currency_id_a, currency_id_b);
+			PausedPairs::<T>::insert(pair, true);
+
+			Self::deposit_event(Event::PairPaused(pair.0, pair.1));
+			Ok(().into())
+		}
+
+		/// Resume trading of a pair, also lifting a circuit breaker halt. The reference price is
+		/// cleared, so the next fill sets a new one.
+		#[pallet::weight(10_000)]
+		pub fn unpause_pair(
+			origin: OriginFor<T>,
+			currency_id_a: CurrencyIdOf<T>,
+			currency_id_b: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::PauseOrigin::ensure_origin(origin)?;
+
+			let pair = Self::trading_pair(currency_id_a, currency_id_b);
+			PausedPairs::<T>::remove(pair);
+			HaltedUntil::<T>::remove(pair);
+			ReferencePrices::<T>::remove(pair);
+
+			Self::deposit_event(Event::PairUnpaused(pair.0, pair.1));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn set_fees(
+			origin: OriginFor<T>,
+			maker: Permill,
+			taker: Permill,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let rates = FeeRate { maker, taker };
+			FeeRates::<T>::put(rates);
+
+			Self::deposit_event(Event::FeesUpdated(rates));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn set_fee_tiers(
+			origin: OriginFor<T>,
+			currency_id: CurrencyIdOf<T>,
+			tiers: Vec<FeeTier<BalanceOf<T>>>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(tiers.len() <= MAX_FEE_TIERS, Error::<T>::InvalidFeeTiers);
+			ensure!(
+				tiers.windows(2).all(|w| w[0].volume < w[1].volume),
+				Error::<T>::InvalidFeeTiers
+			);
+
+			FeeTiers::<T>::insert(currency_id, tiers);
+
+			Self::deposit_event(Event::FeeTiersUpdated(currency_id));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn set_referral_share(
+			origin: OriginFor<T>,
+			share: Permill,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ReferralShare::<T>::put(share);
+
+			Self::deposit_event(Event::ReferralShareUpdated(share));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn set_referrer(
+			origin: OriginFor<T>,
+			referrer: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(referrer != who, Error::<T>::InvalidReferrer);
+
+			Referrers::<T>::try_mutate(&who, |r| -> DispatchResult {
+				ensure!(r.is_none(), Error::<T>::ReferrerAlreadySet);
+				*r = Some(referrer.clone());
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::ReferrerSet(who, referrer));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn do_submit_order(
+		who: T::AccountId,
+		base_currency_id: CurrencyIdOf<T>,
+		base_amount: BalanceOf<T>,
+		target_currency_id: CurrencyIdOf<T>,
+		target_amount: BalanceOf<T>,
+	) -> Result<T::OrderId, DispatchError> {
+		let order = Order {
+			base_currency_id,
+			base_amount,
+			target_currency_id,
+			target_amount,
+			owner: who.clone(),
+			created_at: frame_system::Module::<T>::block_number(),
+		};
+		Self::ensure_trading_allowed(&Self::trading_pair(base_currency_id, target_currency_id))?;
+		Self::ensure_order_size(&order)?;
+
+		NextOrderId::<T>::try_mutate(|id| -> Result<T::OrderId, DispatchError> {
+			let order_id = id.unwrap_or_default();
+
+			*id = Some(
+				order_id
+					.checked_add(&One::one())
+					.ok_or(Error::<T>::OrderIdOverflow)?,
+			);
+
+			T::Currency::reserve(base_currency_id, &who, base_amount)?;
+
+			Orders::<T>::insert(order_id, &order);
+
+			T::OnOrderCreated::on_order_created(&order_id, &order);
+			Self::deposit_event(Event::OrderCreated(order_id, order));
+			Ok(order_id)
+		})
+	}
+
+	/// Settle a fill of `order` by `who`, with the base amount taken from the owner's reserved
+	/// balance if `reserved`, or from their free balance otherwise.
+	fn do_fill(
+		who: &T::AccountId,
+		order_id: T::OrderId,
+		order: &OrderOf<T>,
+		reserved: bool,
+	) -> DispatchResult {
+		let taker_discount = Self::fee_tier(who, order.base_currency_id).taker_discount;
+		let taker_rate = Permill::from_parts(
+			Permill::one().saturating_sub(taker_discount) * Self::fee_rate().taker.deconstruct(),
+		);
+		let maker_fee = Self::maker_fee(order);
+		let taker_fee = taker_rate.mul_floor(order.base_amount);
+
+		Self::ensure_no_dust(order.target_currency_id, who, Zero::zero(), order.target_amount)?;
+		Self::ensure_no_dust(order.target_currency_id, &order.owner, order.target_amount, maker_fee)?;
+		Self::ensure_no_dust(order.base_currency_id, &order.owner, Zero::zero(), order.base_amount)?;
+		Self::ensure_no_dust(order.base_currency_id, who, order.base_amount, taker_fee)?;
+
+		T::Currency::transfer(
+			order.target_currency_id,
+			who,
+			&order.owner,
+			order.target_amount,
+		)?;
+		if reserved {
+			let val = T::Currency::repatriate_reserved(
+				order.base_currency_id,
+				&order.owner,
+				who,
+				order.base_amount,
+				BalanceStatus::Free,
+			)?;
+			ensure!(val.is_zero(), Error::<T>::InsufficientBalance);
+		} else {
+			T::Currency::transfer(order.base_currency_id, &order.owner, who, order.base_amount)?;
+		}
+
+		Self::charge_fee(&order.owner, order.target_currency_id, maker_fee)?;
+		Self::charge_fee(who, order.base_currency_id, taker_fee)?;
+
+		// self-trades don't count, they would buy fee tiers with the fees paid to oneself
+		if *who != order.owner {
+			Self::record_trade(&order.owner, order);
+			Self::record_trade(who, order);
+		}
+
+		T::OnOrderFilled::on_order_filled(who, &order_id, order);
+		Self::deposit_event(Event::OrderFilled(Fill {
+			order_id,
+			maker: order.owner.clone(),
+			taker: who.clone(),
+			base_currency_id: order.base_currency_id,
+			target_currency_id: order.target_currency_id,
+			base_amount: order.base_amount,
+			target_amount: order.target_amount,
+			maker_fee,
+			taker_fee,
+		}));
+		Ok(())
+	}
+
+	fn ensure_order_size(order: &OrderOf<T>) -> DispatchResult {
+		ensure!(
+			order.base_amount >= T::MinimumOrderSize::get(&order.base_currency_id)
+				&& order.target_amount >= T::MinimumOrderSize::get(&order.target_currency_id),
+			Error::<T>::OrderTooSmall
+		);
+		Ok(())
+	}
+
+	/// Settle two crossing orders checked by `check_matches`, removing both from the book.
+	fn do_match(
+		(order_id_a, a): (T::OrderId, OrderOf<T>),
+		(order_id_b, b): (T::OrderId, OrderOf<T>),
+	) -> DispatchResult {
+		let fee_a = Self::maker_fee(&a);
+		let fee_b = Self::maker_fee(&b);
+
+		Self::ensure_no_dust(a.target_currency_id, &a.owner, a.target_amount, fee_a)?;
+		Self::ensure_no_dust(b.target_currency_id, &b.owner, b.target_amount, fee_b)?;
+		Self::ensure_no_dust(a.base_currency_id, &a.owner, Zero::zero(), b.target_amount)?;
+		Self::ensure_no_dust(b.base_currency_id, &b.owner, Zero::zero(), a.target_amount)?;
+
+		for (from, to) in [(&a, &b), (&b, &a)].iter() {
+			let val = T::Currency::repatriate_reserved(
+				from.base_currency_id,
+				&from.owner,
+				&to.owner,
+				to.target_amount,
+				BalanceStatus::Free,
+			)?;
+			ensure!(val.is_zero(), Error::<T>::InsufficientBalance);
+			T::Currency::unreserve(
+				from.base_currency_id,
+				&from.owner,
+				from.base_amount - to.target_amount,
+			);
+		}
+
+		Self::charge_fee(&a.owner, a.target_currency_id, fee_a)?;
+		Self::charge_fee(&b.owner, b.target_currency_id, fee_b)?;
+
+		if a.owner != b.owner {
+			Self::record_trade(&a.owner, &a);
+			Self::record_trade(&b.owner, &b);
+		}
+
+		Orders::<T>::remove(order_id_a);
+		Orders::<T>::remove(order_id_b);
+		DelegatedOrders::<T>::remove(order_id_a);
+		DelegatedOrders::<T>::remove(order_id_b);
+
+		T::OnOrderFilled::on_order_filled(&b.owner, &order_id_a, &a);
+		T::OnOrderFilled::on_order_filled(&a.owner, &order_id_b, &b);
+
+		let fills = [((order_id_a, &a, fee_a), &b), ((order_id_b, &b, fee_b), &a)];
+		for ((order_id, order, fee), counter) in fills.iter() {
+			// each order pays what the other asked for, the rest is returned
+			let refund = order.base_amount - counter.target_amount;
+			Self::deposit_event(Event::OrderFilled(Fill {
+				order_id: *order_id,
+				maker: order.owner.clone(),
+				taker: counter.owner.clone(),
+				base_currency_id: order.base_currency_id,
+				target_currency_id: order.target_currency_id,
+				base_amount: counter.target_amount,
+				target_amount: order.target_amount,
+				maker_fee: *fee,
+				taker_fee: Zero::zero(),
+				}));
+			if !refund.is_zero() {
+				Self::deposit_event(Event::OrderRefunded(
+					*order_id,
+					order.owner.clone(),
+					order.base_currency_id,
+					refund,
+				));
+			}
+		}
+		Ok(())
+	}
+
+	/// Fee paid by the owner of `order` on the `target_amount` they receive.
+	fn maker_fee(order: &OrderOf<T>) -> BalanceOf<T> {
+		let maker_discount = Self::fee_tier(&order.owner, order.target_currency_id).maker_discount;
+		let rate = Permill::from_parts(
+			Permill::one().saturating_sub(maker_discount) * Self::fee_rate().maker.deconstruct(),
+		);
+		rate.mul_floor(order.target_amount)
+	}
+
+	/// Whether `a` and `b` trade opposite currencies and can both be filled in full.
+	fn crosses(a: &OrderOf<T>, b: &OrderOf<T>) -> bool {
+		a.base_currency_id == b.target_currency_id
+			&& a.target_currency_id == b.base_currency_id
+			&& a.base_amount >= b.target_amount
+			&& b.base_amount >= a.target_amount
+	}
+
+	/// Check that `matches` are distinct existing orders crossing each other, on pairs open for
+	/// trading, and return them.
+	#[allow(clippy::type_complexity)]
+	fn check_matches(
+		matches: &[(T::OrderId, T::OrderId)],
+	) -> Result<Vec<((T::OrderId, OrderOf<T>), (T::OrderId, OrderOf<T>))>, Error<T>> {
+		ensure!(!matches.is_empty() && matches.len() <= MAX_MATCHES, Error::<T>::InvalidMatch);
+
+		let mut seen = Vec::with_capacity(matches.len() * 2);
+		matches
+			.iter()
+			.map(|&(order_id_a, order_id_b)| {
+				ensure!(
+					order_id_a != order_id_b && !seen.contains(&order_id_a) && !seen.contains(&order_id_b),
+					Error::<T>::InvalidMatch
+				);
+				seen.push(order_id_a);
+				seen.push(order_id_b);
+
+				let a = Self::orders(order_id_a).ok_or(Error::<T>::InvalidMatch)?;
+				let b = Self::orders(order_id_b).ok_or(Error::<T>::InvalidMatch)?;
+				ensure!(Self::crosses(&a, &b), Error::<T>::InvalidMatch);
+				ensure!(
+					Self::delegated_orders(order_id_a).as_ref() != Some(&b.owner)
+						&& Self::delegated_orders(order_id_b).as_ref() != Some(&a.owner),
+					Error::<T>::InvalidMatch
+				);
+				ensure!(
+					Self::trading_allowed(&Self::trading_pair(a.base_currency_id, a.target_currency_id)),
+					Error::<T>::InvalidMatch
+				);
+				Ok(((order_id_a, a), (order_id_b, b)))
+			})
+			.collect()
+	}
+
+	/// Up to `MAX_MATCHES` pairs of crossing orders, each order matched with the oldest order
+	/// it crosses.
+	pub fn find_matches() -> Vec<(T::OrderId, T::OrderId)> {
+		let mut orders = Orders::<T>::iter()
+			.filter(|(_, order)| {
+				Self::trading_allowed(&Self::trading_pair(order.base_currency_id, order.target_currency_id))
+			})
+			.collect::<Vec<_>>();
+		orders.sort_by_key(|(order_id, _)| *order_id);
+
+		let mut matched = Vec::new();
+		let mut matches = Vec::new();
+		for (i, (order_id_a, a)) in orders.iter().enumerate() {
+			if matches.len() >= MAX_MATCHES {
+				break;
+			}
+			if matched.contains(order_id_a) {
+				continue;
+			}
+			if let Some((order_id_b, _)) = orders[i + 1..]
+				.iter()
+				.find(|(order_id_b, b)| !matched.contains(order_id_b) && Self::crosses(a, b))
+			{
+				matched.push(*order_id_a);
+				matched.push(*order_id_b);
+				matches.push((*order_id_a, *order_id_b));
+			}
+		}
+		matches
+	}
+
+	/// Check the invariants of the exchange state: every open order is covered by the reserved
+	/// balance of its owner, `NextOrderId` is above every order id and only open orders have a
+	/// delegate.
+	#[cfg(any(feature = "try-runtime", feature = "fuzzing", test))]
+	pub fn try_state() -> Result<(), &'static str> {
+		let next_order_id = Self::next_orderid();
+		let mut reserved: Vec<((T::AccountId, CurrencyIdOf<T>), BalanceOf<T>)> = Vec::new();
+		for (order_id, order) in Orders::<T>::iter() {
+			if next_order_id.map_or(true, |next| next <= order_id) {
+				return Err("NextOrderId does not exceed all order ids");
+			}
+			let key = (order.owner, order.base_currency_id);
+			match reserved.iter_mut().find(|(k, _)| *k == key) {
+				Some((_, amount)) => *amount = amount.saturating_add(order.base_amount),
+				None => reserved.push((key, order.base_amount)),
+			}
+		}
+		for ((owner, currency_id), amount) in reserved {
+			if T::Currency::reserved_balance(currency_id, &owner) < amount {
+				return Err("open orders are not covered by reserved balances");
+			}
+		}
+		if DelegatedOrders::<T>::iter().any(|(order_id, _)| !Orders::<T>::contains_key(order_id)) {
+			return Err("delegate recorded for a closed order");
+		}
+		Ok(())
+	}
+
+	/// Message the owner of `signed_order` signs, bound to this chain by its genesis hash as
+	/// `CheckGenesis` does for extrinsics.
+	pub fn signed_order_payload(signed_order: &SignedOrderOf<T>) -> Vec<u8> {
+		let mut payload = SIGNED_ORDER_CONTEXT.to_vec();
+		frame_system::Module::<T>::block_hash(T::BlockNumber::zero()).encode_to(&mut payload);
+		signed_order.encode_to(&mut payload);
+		payload
+	}
+
+	/// The pair of two currencies, in a canonical order independent of the trade direction.
+	pub fn trading_pair(a: CurrencyIdOf<T>, b: CurrencyIdOf<T>) -> PairOf<T> {
+		trading_pair(a, b)
+	}
+
+	/// Price of `order` in units of `pair.1` per unit of `pair.0`.
+	fn order_price(pair: &PairOf<T>, order: &OrderOf<T>) -> Option<FixedU128> {
+		let (amount_0, amount_1) = if order.base_currency_id == pair.0 {
+			(order.base_amount, order.target_amount)
+		} else {
+			(order.target_amount, order.base_amount)
+		};
+		FixedU128::checked_from_rational(
+			UniqueSaturatedInto::<u128>::unique_saturated_into(amount_1),
+			UniqueSaturatedInto::<u128>::unique_saturated_into(amount_0),
+		)
+	}
+
+	/// Read-only version of `ensure_trading_allowed`.
+	fn trading_allowed(pair: &PairOf<T>) -> bool {
+		Self::listed_pairs(pair)
+			&& !Self::trading_paused()
+			&& !Self::paused_pairs(pair)
+			&& Self::halted_until(pair)
+				.map_or(true, |until| frame_system::Module::<T>::block_number() >= until)
+	}
+
+	fn ensure_trading_allowed(pair: &PairOf<T>) -> DispatchResult {
+		ensure!(Self::listed_pairs(pair), Error::<T>::PairNotListed);
+		ensure!(!Self::trading_paused(), Error::<T>::TradingPaused);
+		ensure!(!Self::paused_pairs(pair), Error::<T>::PairPaused);
+		if let Some(until) = Self::halted_until(pair) {
+			ensure!(
+				frame_system::Module::<T>::block_number() >= until,
+				Error::<T>::PairHalted
+			);
+			HaltedUntil::<T>::remove(pair);
+		}
+		Ok(())
+	}
+
+	/// Record the price of a fill of `order` by `taker` as the new reference price, or halt the
+	/// pair if it deviates too much from the current one. Returns whether the fill can go ahead.
+	///
+	/// Self-fills and fills below `CircuitBreakerMinimumSize` are ignored, so they can neither
+	/// move the reference nor halt the pair. A halt leaves the reference unchanged: trading
+	/// resumes at the previous level, and only `unpause_pair` resets it.
+	fn check_circuit_breaker(pair: &PairOf<T>, order: &OrderOf<T>, taker: &T::AccountId) -> bool {
+		if *taker == order.owner
+			|| order.base_amount < T::CircuitBreakerMinimumSize::get(&order.base_currency_id)
+			|| order.target_amount < T::CircuitBreakerMinimumSize::get(&order.target_currency_id)
+		{
+			return true;
+		}
+		let price = match Self::order_price(pair, order) {
+			Some(price) => price,
+			None => return true,
+		};
+
+		if let Some(reference) = Self::reference_prices(pair) {
+			let deviation = if price > reference {
+				price.saturating_sub(reference)
+			} else {
+				reference.saturating_sub(price)
+			};
+			let threshold = FixedU128::saturating_from_rational(
+				T::CircuitBreakerThreshold::get().deconstruct(),
+				Permill::ACCURACY,
+			);
+			if deviation > reference.saturating_mul(threshold) {
+				let until =
+					frame_system::Module::<T>::block_number() + T::CircuitBreakerDuration::get();
+				HaltedUntil::<T>::insert(pair, until);
+				Self::deposit_event(Event::CircuitBreakerTriggered(pair.0, pair.1, price, until));
+				return false;
+			}
+		}
+
+		ReferencePrices::<T>::insert(pair, price);
+		true
+	}
+
+	/// Index of the day the current block belongs to.
+	fn current_day() -> T::BlockNumber {
+		frame_system::Module::<T>::block_number() / T::BlocksPerDay::get()
+	}
+
+	/// Volume traded by `who` in `currency_id` over the last `VOLUME_WINDOW_DAYS` days.
+	pub fn rolling_volume(who: &T::AccountId, currency_id: CurrencyIdOf<T>) -> BalanceOf<T> {
+		let today = Self::current_day();
+		Self::trading_volumes(who, currency_id)
+			.into_iter()
+			.filter(|(day, _)| day.saturating_add(VOLUME_WINDOW_DAYS.into()) > today)
+			.fold(Zero::zero(), |acc: BalanceOf<T>, (_, volume)| acc.saturating_add(volume))
+	}
+
+	/// Highest fee tier of `currency_id` reached by `who`.
+	pub fn fee_tier(who: &T::AccountId, currency_id: CurrencyIdOf<T>) -> FeeTier<BalanceOf<T>> {
+		let tiers = Self::fee_tiers(currency_id);
+		if tiers.is_empty() {
+			return Default::default();
+		}
+		let volume = Self::rolling_volume(who, currency_id);
+		tiers
+			.into_iter()
+			.rev()
+			.find(|tier| tier.volume <= volume)
+			.unwrap_or_default()
+	}
+
+	fn record_volume(who: &T::AccountId, currency_id: CurrencyIdOf<T>, amount: BalanceOf<T>) {
+		let today = Self::current_day();
+		TradingVolumes::<T>::mutate(who, currency_id, |buckets| {
+			buckets.retain(|(day, _)| day.saturating_add(VOLUME_WINDOW_DAYS.into()) > today);
+			match buckets.last_mut() {
+				Some((day, volume)) if *day == today => *volume = volume.saturating_add(amount),
+				_ => buckets.push((today, amount)),
+			}
+		});
+	}
+
+	/// Both sides of a fill count towards the volume of each party, unless they are the same.
+	fn record_trade(who: &T::AccountId, order: &OrderOf<T>) {
+		Self::record_volume(who, order.base_currency_id, order.base_amount);
+		Self::record_volume(who, order.target_currency_id, order.target_amount);
+	}
+
+	/// Mark `nonce` of `owner` as used until `expiry`.
+	fn use_nonce(owner: &T::AccountId, nonce: u64, expiry: T::BlockNumber) {
+		UsedNonces::<T>::insert(owner, nonce, true);
+		NonceExpiries::<T>::insert(expiry, (owner.clone(), nonce), ());
+	}
+
+	/// Ensure that crediting `increase` to and debiting `decrease` from `who` does not leave
+	/// them with a non-zero balance below the existential deposit, which would be lost as dust.
+	fn ensure_no_dust(
+		currency_id: CurrencyIdOf<T>,
+		who: &T::AccountId,
+		increase: BalanceOf<T>,
+		decrease: BalanceOf<T>,
+	) -> DispatchResult {
+		let total = T::Currency::total_balance(currency_id, who)
+			.saturating_add(increase)
+			.saturating_sub(decrease);
+		ensure!(
+			total.is_zero() || total >= T::Currency::minimum_balance(currency_id),
+			Error::<T>::WouldLeaveDust
+		);
+		Ok(())
+	}
+
+	/// Charge `fee` to `who`, sharing it with their referrer if they have one.
+	///
+	/// A transfer that would leave its recipient with dust is skipped: the referral reward then
+	/// goes to `FeeCollector`, and what `FeeCollector` can't receive isn't charged.
+	fn charge_fee(
+		who: &T::AccountId,
+		currency_id: CurrencyIdOf<T>,
+		fee: BalanceOf<T>,
+	) -> DispatchResult {
+		if fee.is_zero() {
+			return Ok(());
+		}
+
+		let mut referral_reward = Zero::zero();
+		if let Some(referrer) = Self::referrers(who) {
+			referral_reward = Self::referral_share().mul_floor(fee);
+			if Self::ensure_no_dust(currency_id, &referrer, referral_reward, Zero::zero()).is_err() {
+				referral_reward = Zero::zero();
+			}
+			if !referral_reward.is_zero() {
+				T::Currency::transfer(currency_id, who, &referrer, referral_reward)?;
+				ReferralEarnings::<T>::mutate(&referrer, currency_id, |earned| {
+					*earned = earned.saturating_add(referral_reward)
+				});
+				Self::deposit_event(Event::ReferralRewardPaid(
+					referrer,
+					who.clone(),
+					currency_id,
+					referral_reward,
+				));
+			}
+		}
+
+		let collected = fee.saturating_sub(referral_reward);
+		let fee_collector = T::FeeCollector::get();
+		if Self::ensure_no_dust(currency_id, &fee_collector, collected, Zero::zero()).is_err() {
+			return Ok(());
+		}
+		T::Currency::transfer(currency_id, who, &fee_collector, collected)
 	}
 }
@@ -0,0 +1,239 @@
+//! Storage migrations.
+//!
+//! `StorageVersion` records the layout of the pallet's storage. New chains start at `LATEST`,
+//! and `migrate` runs on every runtime upgrade, applying the migrations from the recorded version
+//! up to `LATEST` in order. Changing the encoding of a stored type requires a new `Releases`
+//! variant and a module here decoding the previous format, with `pre_migrate` and
+//! `post_migrate` checks covered by tests on fixtures of the previous format.
+
+use super::*;
+use frame_support::{
+	storage::migration::{StorageIterator, StorageKeyIterator},
+	traits::{Get, PalletInfo},
+	weights::Weight,
+	Twox64Concat,
+};
+
+/// Storage version of the current code.
+pub const LATEST: Releases = Releases::V3;
+
+/// Bring the storage from its recorded version to `LATEST`.
+///
+/// With the `try-runtime` feature the migrations are checked by `pre_upgrade` and
+/// `post_upgrade`, panicking if they fail.
+pub fn migrate<T: Config>() -> Weight {
+	#[cfg(feature = "try-runtime")]
+	let checked = pre_upgrade::<T>().expect("exchange pre_upgrade checks failed");
+
+	let mut weight = T::DbWeight::get().reads(2);
+	if StorageVersion::<T>::get() == Releases::V0 {
+		weight = weight.saturating_add(v1::migrate::<T>());
+	}
+	if StorageVersion::<T>::get() == Releases::V1 {
+		weight = weight.saturating_add(v2::migrate::<T>());
+	}
+	if StorageVersion::<T>::get() == Releases::V2 {
+		weight = weight.saturating_add(v3::migrate::<T>());
+	}
+
+	#[cfg(feature = "try-runtime")]
+	post_upgrade::<T>(checked).expect("exchange post_upgrade checks failed");
+	weight
+}
+
+/// What `pre_upgrade` records for `post_upgrade`: the storage version before the upgrade, the
+/// number of orders to migrate to `Releases::V1` and of delegates to migrate to `Releases::V3`.
+pub type PreUpgrade = (Releases, Option<u32>, Option<u32>);
+
+/// Checks of the migrations about to run, returning what `post_upgrade` needs.
+#[cfg(any(feature = "try-runtime", test))]
+pub fn pre_upgrade<T: Config>() -> Result<PreUpgrade, &'static str> {
+	let version = StorageVersion::<T>::get();
+	let v1_orders = match version {
+		Releases::V0 => Some(v1::pre_migrate::<T>()?),
+		Releases::V1 | Releases::V2 | Releases::V3 => None,
+	};
+	let v3_delegates = match version {
+		Releases::V0 | Releases::V1 | Releases::V2 => Some(v3::pre_migrate::<T>()?),
+		Releases::V3 => None,
+	};
+	Ok((version, v1_orders, v3_delegates))
+}
+
+/// Checks of the migrations that ran and of the state invariants after them.
+#[cfg(any(feature = "try-runtime", test))]
+pub fn post_upgrade<T: Config>(
+	(version, v1_orders, v3_delegates): PreUpgrade,
+) -> Result<(), &'static str> {
+	if let Some(count) = v1_orders {
+		v1::post_migrate::<T>(count)?;
+	}
+	if matches!(version, Releases::V0 | Releases::V1) {
+		v2::post_migrate::<T>()?;
+	}
+	if let Some(count) = v3_delegates {
+		v3::post_migrate::<T>(count)?;
+	}
+	if StorageVersion::<T>::get() != LATEST {
+		return Err("exchange storage is not at the latest version");
+	}
+	Pallet::<T>::try_state()
+}
+
+/// Adds `Order::created_at`.
+pub mod v1 {
+	use super::*;
+
+	/// `Order` of `Releases::V0`.
+	#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+	pub struct OldOrder<CurrencyId, Balance, AccountId> {
+		pub base_currency_id: CurrencyId,
+		#[codec(compact)]
+		pub base_amount: Balance,
+		pub target_currency_id: CurrencyId,
+		#[codec(compact)]
+		pub target_amount: Balance,
+		pub owner: AccountId,
+	}
+
+	pub type OldOrderOf<T> = OldOrder<CurrencyIdOf<T>, BalanceOf<T>, <T as frame_system::Config>::AccountId>;
+
+	/// Rewrite the open orders with the upgrade block as `created_at`, their creation block
+	/// being unknown.
+	pub fn migrate<T: Config>() -> Weight {
+		let now = frame_system::Module::<T>::block_number();
+		let mut count: Weight = 0;
+		Orders::<T>::translate::<OldOrderOf<T>, _>(|_, old| {
+			count += 1;
+			Some(Order {
+				base_currency_id: old.base_currency_id,
+				base_amount: old.base_amount,
+				target_currency_id: old.target_currency_id,
+				target_amount: old.target_amount,
+				owner: old.owner,
+				created_at: now,
+			})
+		});
+		StorageVersion::<T>::put(Releases::V1);
+
+		T::DbWeight::get().reads_writes(count, count + 1)
+	}
+
+	/// Check the storage can be migrated, returning the number of orders to migrate.
+	pub fn pre_migrate<T: Config>() -> Result<u32, &'static str> {
+		if StorageVersion::<T>::get() != Releases::V0 {
+			return Err("exchange storage is not V0");
+		}
+		let pallet = <T as frame_system::Config>::PalletInfo::name::<Pallet<T>>()
+			.ok_or("exchange pallet is not in the runtime")?;
+		Ok(StorageKeyIterator::<T::OrderId, OldOrderOf<T>, Twox64Concat>::new(pallet.as_bytes(), b"Orders").count() as u32)
+	}
+
+	/// Check the `count` orders found by `pre_migrate` were all migrated.
+	pub fn post_migrate<T: Config>(count: u32) -> Result<(), &'static str> {
+		// later migrations may have run since
+		if StorageVersion::<T>::get() == Releases::V0 {
+			return Err("exchange storage was not migrated to V1");
+		}
+		// `iter` skips values that fail to decode
+		if Orders::<T>::iter().count() as u32 != count {
+			return Err("exchange orders were lost in the migration");
+		}
+		Ok(())
+	}
+}
+
+/// Lists the pairs of the open orders.
+pub mod v2 {
+	use super::*;
+
+	/// List the pair of every open order, pairs being tradable without listing before.
+	pub fn migrate<T: Config>() -> Weight {
+		let mut count: Weight = 0;
+		for (_, order) in Orders::<T>::iter() {
+			count += 1;
+			let pair = trading_pair(order.base_currency_id, order.target_currency_id);
+			ListedPairs::<T>::insert(pair, true);
+		}
+		StorageVersion::<T>::put(Releases::V2);
+
+		T::DbWeight::get().reads_writes(count, count + 1)
+	}
+
+	/// Check the pair of every open order is listed.
+	pub fn post_migrate<T: Config>() -> Result<(), &'static str> {
+		// later migrations may have run since
+		if matches!(StorageVersion::<T>::get(), Releases::V0 | Releases::V1) {
+			return Err("exchange storage was not migrated to V2");
+		}
+		if Orders::<T>::iter().any(|(_, order)| {
+			!Pallet::<T>::listed_pairs(trading_pair(order.base_currency_id, order.target_currency_id))
+		}) {
+			return Err("pairs of open orders are not listed");
+		}
+		Ok(())
+	}
+}
+
+/// Limits the notional of delegates per base currency.
+pub mod v3 {
+	use super::*;
+
+	/// `DelegatePermissions` of `Releases::V2`, whose `max_notional` added up the amounts of
+	/// every currency.
+	#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+	pub struct OldDelegatePermissions<CurrencyId, Balance> {
+		pub can_submit: bool,
+		pub can_cancel: bool,
+		pub pairs: Option<Vec<(CurrencyId, CurrencyId)>>,
+		pub max_notional: Option<Balance>,
+	}
+
+	pub type OldDelegatePermissionsOf<T> = OldDelegatePermissions<CurrencyIdOf<T>, BalanceOf<T>>;
+
+	/// Rewrite the delegates' permissions. A limit across currencies can't be split between them,
+	/// so limited delegates can't sell any currency until authorized again, and the notional they
+	/// used is cleared.
+	pub fn migrate<T: Config>() -> Weight {
+		let mut count: Weight = 0;
+		Delegates::<T>::translate::<OldDelegatePermissionsOf<T>, _>(|_, _, old| {
+			count += 1;
+			Some(DelegatePermissions {
+				can_submit: old.can_submit,
+				can_cancel: old.can_cancel,
+				pairs: old.pairs,
+				max_notional: old.max_notional.map(|_| Vec::new()),
+			})
+		});
+		DelegatedNotional::<T>::remove_all();
+		StorageVersion::<T>::put(Releases::V3);
+
+		T::DbWeight::get().reads_writes(count, count + 2)
+	}
+
+	/// Check the storage can be migrated, returning the number of delegates to migrate.
+	pub fn pre_migrate<T: Config>() -> Result<u32, &'static str> {
+		if StorageVersion::<T>::get() == Releases::V3 {
+			return Err("exchange storage is already V3");
+		}
+		let pallet = <T as frame_system::Config>::PalletInfo::name::<Pallet<T>>()
+			.ok_or("exchange pallet is not in the runtime")?;
+		let delegates = StorageIterator::<OldDelegatePermissionsOf<T>>::new(pallet.as_bytes(), b"Delegates");
+		Ok(delegates.count() as u32)
+	}
+
+	/// Check the `count` delegates found by `pre_migrate` were all migrated.
+	pub fn post_migrate<T: Config>(count: u32) -> Result<(), &'static str> {
+		if StorageVersion::<T>::get() != Releases::V3 {
+			return Err("exchange storage was not migrated to V3");
+		}
+		// `iter` skips values that fail to decode
+		if Delegates::<T>::iter().count() as u32 != count {
+			return Err("exchange delegates were lost in the migration");
+		}
+		if DelegatedNotional::<T>::iter().next().is_some() {
+			return Err("notional of delegates was not cleared");
+		}
+		Ok(())
+	}
+}
@@ -1,15 +1,18 @@
 use crate as pallet_exchange;
 use frame_support::parameter_types;
 use frame_support::sp_runtime::{
-    testing::Header,
+    testing::{Header, TestSignature, UintAuthorityId},
     traits::{BlakeTwo256, IdentityLookup, Zero},
+    Permill,
 };
 use frame_system as system;
+use frame_system::EnsureRoot;
 use sp_core::H256;
 
-use orml_traits::parameter_type_with_key;
+use orml_traits::{parameter_type_with_key, MultiCurrency, MultiReservableCurrency};
 
 use frame_support::traits::GenesisBuild;
+use std::cell::RefCell;
 
 pub type Amount = i128;
 pub type AccountId = u64;
@@ -19,9 +22,10 @@ pub type BlockNumber = u64;
 
 pub const ALICE: AccountId = 1;
 pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
 
 pub const DOT: CurrencyId = 1;
-//pub const KSM: CurrencyId = 2;
+pub const KSM: CurrencyId = 2;
 pub const BTC: CurrencyId = 3;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -36,7 +40,7 @@ frame_support::construct_runtime!(
     {
         System: frame_system::{Module, Call, Config, Storage, Event<T>},
         Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
-        ExchangeModule: pallet_exchange::{Module, Call, Storage, Event<T>},
+        ExchangeModule: pallet_exchange::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
         Currencies: orml_currencies::{Module, Call, Event<T>},
         Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
     }
@@ -73,8 +77,12 @@ impl system::Config for Test {
 }
 
 parameter_type_with_key! {
-    pub ExistentialDeposits: |_currency_id: CurrencyId| -> Balance {
-        Zero::zero()
+    pub ExistentialDeposits: |currency_id: CurrencyId| -> Balance {
+        if *currency_id == KSM {
+            100
+        } else {
+            Zero::zero()
+        }
     };
 }
 
@@ -116,19 +124,93 @@ impl pallet_balances::Config for Test {
     type WeightInfo = ();
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrderHook {
+    Created(u32),
+    Filled(AccountId, u32),
+    Cancelled(u32),
+}
+
+thread_local! {
+    pub static ORDER_HOOKS: RefCell<Vec<OrderHook>> = RefCell::new(vec![]);
+}
+
+pub fn order_hooks() -> Vec<OrderHook> {
+    ORDER_HOOKS.with(|v| v.borrow().clone())
+}
+
+pub struct MockOrderHooks;
+impl crate::OnOrderCreated<u32, crate::OrderOf<Test>> for MockOrderHooks {
+    fn on_order_created(order_id: &u32, _order: &crate::OrderOf<Test>) {
+        ORDER_HOOKS.with(|v| v.borrow_mut().push(OrderHook::Created(*order_id)));
+    }
+}
+impl crate::OnOrderFilled<AccountId, u32, crate::OrderOf<Test>> for MockOrderHooks {
+    fn on_order_filled(taker: &AccountId, order_id: &u32, _order: &crate::OrderOf<Test>) {
+        ORDER_HOOKS.with(|v| v.borrow_mut().push(OrderHook::Filled(*taker, *order_id)));
+    }
+}
+impl crate::OnOrderCancelled<u32, crate::OrderOf<Test>> for MockOrderHooks {
+    fn on_order_cancelled(order_id: &u32, _order: &crate::OrderOf<Test>) {
+        ORDER_HOOKS.with(|v| v.borrow_mut().push(OrderHook::Cancelled(*order_id)));
+    }
+}
+
+parameter_type_with_key! {
+    pub MinimumOrderSizes: |_currency_id: CurrencyId| -> Balance {
+        1
+    };
+}
+
+parameter_type_with_key! {
+    pub CircuitBreakerMinimumSizes: |_currency_id: CurrencyId| -> Balance {
+        10
+    };
+}
+
+parameter_types! {
+    pub const FeeCollector: AccountId = 99;
+    pub const BlocksPerDay: BlockNumber = 10;
+    pub const CircuitBreakerThreshold: Permill = Permill::from_percent(10);
+    pub const CircuitBreakerDuration: BlockNumber = 5;
+    pub const UnsignedPriority: u64 = 1 << 20;
+}
+
 impl pallet_exchange::Config for Test {
     type Event = Event;
     type Currency = Currencies;
     type OrderId = u32;
+    type OnOrderCreated = MockOrderHooks;
+    type OnOrderFilled = MockOrderHooks;
+    type OnOrderCancelled = MockOrderHooks;
+    type UpdateOrigin = EnsureRoot<AccountId>;
+    type FeeCollector = FeeCollector;
+    type BlocksPerDay = BlocksPerDay;
+    type PauseOrigin = EnsureRoot<AccountId>;
+    type CircuitBreakerThreshold = CircuitBreakerThreshold;
+    type CircuitBreakerDuration = CircuitBreakerDuration;
+    type MinimumOrderSize = MinimumOrderSizes;
+    type CircuitBreakerMinimumSize = CircuitBreakerMinimumSizes;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type UnsignedPriority = UnsignedPriority;
 }
 
-// Build genesis storage according to the mock runtime.
-// pub fn new_test_ext() -> sp_io::TestExternalities {
-// 	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
-// }
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+    Call: From<C>,
+{
+    type OverarchingCall = Call;
+    type Extrinsic = UncheckedExtrinsic;
+}
 
+/// Genesis of the mock runtime. By default ALICE and BOB hold DOT and BTC, BOB holds some KSM,
+/// the pairs of DOT, KSM and BTC are listed and trading is free of fees.
 pub struct ExtBuilder {
     endowed_accounts: Vec<(AccountId, CurrencyId, Balance)>,
+    orders: Vec<(AccountId, CurrencyId, Balance, CurrencyId, Balance)>,
+    listed_pairs: Vec<(CurrencyId, CurrencyId)>,
+    fee_rate: crate::FeeRate,
 }
 
 impl Default for ExtBuilder {
@@ -139,13 +221,54 @@ impl Default for ExtBuilder {
                 (BOB, DOT, 1000_000_000_000_000u128),
                 (ALICE, BTC, 1000_000_000_000_000u128),
                 (BOB, BTC, 1000_000_000_000_000u128),
+                (BOB, KSM, 1000u128),
             ],
+            orders: vec![],
+            listed_pairs: vec![(DOT, BTC), (DOT, KSM), (BTC, KSM)],
+            fee_rate: Default::default(),
         }
     }
 }
 
 impl ExtBuilder {
+    /// Set the genesis balance of `who` in `currency_id`, replacing the default endowment.
+    pub fn balance(mut self, who: AccountId, currency_id: CurrencyId, amount: Balance) -> Self {
+        self.endowed_accounts
+            .retain(|(account, currency, _)| (*account, *currency) != (who, currency_id));
+        self.endowed_accounts.push((who, currency_id, amount));
+        self
+    }
+
+    /// Seed the book with an order placed at genesis.
+    pub fn order(
+        mut self,
+        owner: AccountId,
+        base_currency_id: CurrencyId,
+        base_amount: Balance,
+        target_currency_id: CurrencyId,
+        target_amount: Balance,
+    ) -> Self {
+        self.orders
+            .push((owner, base_currency_id, base_amount, target_currency_id, target_amount));
+        self
+    }
+
+    /// List only `pairs`, instead of the pairs of DOT, KSM and BTC.
+    pub fn pairs(mut self, pairs: Vec<(CurrencyId, CurrencyId)>) -> Self {
+        self.listed_pairs = pairs;
+        self
+    }
+
+    /// Charge `maker` and `taker` fees from genesis.
+    pub fn fees(mut self, maker: Permill, taker: Permill) -> Self {
+        self.fee_rate = crate::FeeRate { maker, taker };
+        self
+    }
+
+    /// Build the externalities, at block 1 so that events are recorded.
     pub fn build(self) -> sp_io::TestExternalities {
+        ORDER_HOOKS.with(|v| v.borrow_mut().clear());
+
         let mut t = frame_system::GenesisConfig::default()
             .build_storage::<Test>()
             .unwrap();
@@ -156,6 +279,122 @@ impl ExtBuilder {
         .assimilate_storage(&mut t)
         .unwrap();
 
-        t.into()
+        pallet_exchange::GenesisConfig::<Test> {
+            listed_pairs: self.listed_pairs,
+            fee_rate: self.fee_rate,
+            orders: self.orders,
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        let mut ext: sp_io::TestExternalities = t.into();
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+}
+
+/// Take the events deposited since the last call.
+pub fn events() -> Vec<Event> {
+    let evt = System::events()
+        .into_iter()
+        .map(|evt| evt.event)
+        .collect::<Vec<_>>();
+    System::reset_events();
+    evt
+}
+
+/// Assert the events deposited since the last call of `events` are exactly `expected`.
+pub fn assert_events(expected: Vec<Event>) {
+    assert_eq!(events(), expected);
+}
+
+/// Assert the events deposited since the last call of `events` are exactly `expected`, ignoring
+/// the events of other pallets than the exchange.
+pub fn assert_exchange_events(expected: Vec<crate::Event<Test>>) {
+    let exchange_events = events()
+        .into_iter()
+        .filter_map(|evt| match evt {
+            Event::pallet_exchange(evt) => Some(evt),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(exchange_events, expected);
+}
+
+/// Which balance of an account `BalanceSnapshot` compares.
+#[derive(Clone, Copy, Debug)]
+enum BalanceKind {
+    Free,
+    Reserved,
+}
+
+impl BalanceKind {
+    fn of(self, who: &AccountId, currency_id: CurrencyId) -> Balance {
+        match self {
+            BalanceKind::Free => Tokens::free_balance(currency_id, who),
+            BalanceKind::Reserved => Tokens::reserved_balance(currency_id, who),
+        }
+    }
+}
+
+/// Free and reserved balances of some accounts, to assert how they changed since.
+pub struct BalanceSnapshot(Vec<(AccountId, CurrencyId, Balance, Balance)>);
+
+impl BalanceSnapshot {
+    /// Record the balances of every account of `accounts` in every currency of `currencies`.
+    pub fn take(accounts: &[AccountId], currencies: &[CurrencyId]) -> Self {
+        let mut balances = vec![];
+        for who in accounts {
+            for &currency_id in currencies {
+                balances.push((
+                    *who,
+                    currency_id,
+                    BalanceKind::Free.of(who, currency_id),
+                    BalanceKind::Reserved.of(who, currency_id),
+                ));
+            }
+        }
+        Self(balances)
+    }
+
+    /// Assert the free balances recorded changed by `deltas`, and the others didn't change.
+    pub fn assert_free_deltas(&self, deltas: &[(AccountId, CurrencyId, Amount)]) {
+        self.assert_deltas(deltas, BalanceKind::Free);
+    }
+
+    /// Assert the reserved balances recorded changed by `deltas`, and the others didn't change.
+    pub fn assert_reserved_deltas(&self, deltas: &[(AccountId, CurrencyId, Amount)]) {
+        self.assert_deltas(deltas, BalanceKind::Reserved);
+    }
+
+    fn assert_deltas(&self, deltas: &[(AccountId, CurrencyId, Amount)], kind: BalanceKind) {
+        for (who, currency_id, _) in deltas {
+            assert!(
+                self.0
+                    .iter()
+                    .any(|(account, currency, _, _)| (account, currency) == (who, currency_id)),
+                "no snapshot of {} in {}",
+                who,
+                currency_id
+            );
+        }
+        for &(who, currency_id, free, reserved) in &self.0 {
+            let before = match kind {
+                BalanceKind::Free => free,
+                BalanceKind::Reserved => reserved,
+            };
+            let expected = deltas
+                .iter()
+                .find(|(account, currency, _)| (*account, *currency) == (who, currency_id))
+                .map_or(0, |(_, _, delta)| *delta);
+            assert_eq!(
+                kind.of(&who, currency_id) as Amount - before as Amount,
+                expected,
+                "{:?} balance of {} in {}",
+                kind,
+                who,
+                currency_id
+            );
+        }
     }
 }
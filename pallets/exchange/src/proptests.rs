@@ -0,0 +1,113 @@
+use crate::harness::{self, Action, Input};
+use proptest::{collection::vec, prelude::*};
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        (any::<u8>(), any::<u8>(), any::<u16>(), any::<u8>(), any::<u16>()).prop_map(
+            |(who, base, base_amount, target, target_amount)| Action::Submit {
+                who,
+                base,
+                base_amount,
+                target,
+                target_amount,
+            }
+        ),
+        (any::<u8>(), any::<u8>()).prop_map(|(who, order_id)| Action::Take { who, order_id }),
+        (any::<u8>(), any::<u8>()).prop_map(|(who, order_id)| Action::Cancel { who, order_id }),
+        (
+            (any::<u8>(), any::<u8>(), any::<u8>(), any::<u16>()),
+            (any::<u8>(), any::<u16>(), any::<u8>(), any::<u8>(), any::<bool>())
+        )
+            .prop_map(
+                |(
+                    (who, owner, base, base_amount),
+                    (target, target_amount, nonce, expiry, forged),
+                )| Action::TakeSigned {
+                    who,
+                    owner,
+                    base,
+                    base_amount,
+                    target,
+                    target_amount,
+                    nonce,
+                    expiry,
+                    forged,
+                }
+            ),
+        (any::<u8>(), any::<u8>(), any::<u8>())
+            .prop_map(|(who, nonce, expiry)| Action::CancelSigned { who, nonce, expiry }),
+        (any::<u8>(), any::<u8>()).prop_map(|(order_id_a, order_id_b)| Action::Match {
+            order_id_a,
+            order_id_b,
+        }),
+        Just(Action::MatchFound),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(who, referrer)| Action::SetReferrer { who, referrer }),
+        (0..=1_000_000u32).prop_map(|share| Action::SetReferralShare { share }),
+        (any::<u8>(), any::<u8>()).prop_map(|(base, target)| Action::UnpausePair { base, target }),
+        (0..16u8).prop_map(|blocks| Action::Advance { blocks }),
+    ]
+}
+
+fn input() -> impl Strategy<Value = Input> {
+    (any::<u16>(), any::<u16>(), vec(action(), 1..64)).prop_map(
+        |(maker_fee, taker_fee, actions)| Input {
+            maker_fee,
+            taker_fee,
+            actions,
+        },
+    )
+}
+
+proptest! {
+    #[test]
+    fn settlement_keeps_invariants(input in input()) {
+        harness::run(&input);
+    }
+}
+
+#[test]
+fn cancel_releases_reserve() {
+    harness::run(&Input {
+        maker_fee: 0,
+        taker_fee: 0,
+        actions: vec![
+            Action::Submit {
+                who: 0,
+                base: 0,
+                base_amount: 10,
+                target: 2,
+                target_amount: 1,
+            },
+            Action::Cancel { who: 0, order_id: 0 },
+        ],
+    });
+}
+
+#[test]
+fn referred_match_with_fees() {
+    harness::run(&Input {
+        maker_fee: 10_000,
+        taker_fee: 20_000,
+        actions: vec![
+            Action::SetReferralShare { share: 500_000 },
+            Action::SetReferrer { who: 0, referrer: 2 },
+            Action::Submit {
+                who: 0,
+                base: 0,
+                base_amount: 1000,
+                target: 2,
+                target_amount: 100,
+            },
+            Action::Submit {
+                who: 1,
+                base: 2,
+                base_amount: 100,
+                target: 0,
+                target_amount: 1000,
+            },
+            Action::MatchFound,
+            Action::Advance { blocks: 10 },
+        ],
+    });
+}
@@ -4,6 +4,8 @@ pub use crate::mock::{
 };
 use crate::{mock::*, Error};
 use frame_support::{assert_noop, assert_ok};
+use frame_support::sp_runtime::testing::TestSignature;
+use sp_core::H256;
 
 const ENDOWED_AMOUNT: u128 = 1_000_000_000_000_000;
 
@@ -46,7 +48,8 @@ fn test_submit_order() {
                     base_amount: 10,
                     target_currency_id: BTC,
                     target_amount: 1,
-                    owner: ALICE
+                    owner: ALICE,
+                    created_at: 1
                 }
             )),]
         );
@@ -89,10 +92,22 @@ fn test_take_order() {
                         base_amount: 10,
                         target_currency_id: BTC,
                         target_amount: 1,
-                        owner: ALICE
+                        owner: ALICE,
+                        created_at: 1
                     }
                 )),
                 Event::orml_currencies(orml_currencies::Event::Transferred(BTC, BOB, ALICE, 1)),
+                Event::pallet_exchange(crate::Event::OrderFilled(Fill {
+                    order_id: 0,
+                    maker: ALICE,
+                    taker: BOB,
+                    base_currency_id: DOT,
+                    target_currency_id: BTC,
+                    base_amount: 10,
+                    target_amount: 1,
+                    maker_fee: 0,
+                    taker_fee: 0,
+                })),
                 Event::pallet_exchange(crate::Event::OrderTaken(
                     BOB,
                     0,
@@ -101,7 +116,8 @@ fn test_take_order() {
                         base_amount: 10,
                         target_currency_id: BTC,
                         target_amount: 1,
-                        owner: ALICE
+                        owner: ALICE,
+                        created_at: 1
                     }
                 ))
             ]
@@ -134,6 +150,8 @@ fn test_cancel_order() {
 
         //id exist, is owner
         assert_ok!(ExchangeModule::cancel_order(Origin::signed(ALICE), 0));
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), ENDOWED_AMOUNT);
+        assert_eq!(Tokens::reserved_balance(DOT, &ALICE), 0);
 
         assert_eq!(
             events().as_slice(),
@@ -145,11 +163,1226 @@ fn test_cancel_order() {
                         base_amount: 10,
                         target_currency_id: BTC,
                         target_amount: 1,
-                        owner: ALICE
+                        owner: ALICE,
+                        created_at: 1
                     }
                 )),
+                Event::pallet_exchange(crate::Event::OrderRefunded(0, ALICE, DOT, 10)),
                 Event::pallet_exchange(crate::Event::OrderCancelled(0))
             ]
         );
     });
 }
+
+#[test]
+fn test_order_hooks() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            BTC,
+            1
+        ));
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            20,
+            BTC,
+            2
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 0));
+        assert_ok!(ExchangeModule::cancel_order(Origin::signed(ALICE), 1));
+
+        //failed calls do not trigger hooks
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 0),
+            Error::<Test>::InvalidOrderId
+        );
+
+        assert_eq!(
+            order_hooks(),
+            vec![
+                OrderHook::Created(0),
+                OrderHook::Created(1),
+                OrderHook::Filled(BOB, 0),
+                OrderHook::Cancelled(1),
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_set_fees() {
+    new_test_ext().execute_with(|| {
+        let maker = Permill::from_percent(1);
+        let taker = Permill::from_percent(2);
+
+        assert_noop!(
+            ExchangeModule::set_fees(Origin::signed(ALICE), maker, taker),
+            frame_support::error::BadOrigin
+        );
+        assert_ok!(ExchangeModule::set_fees(Origin::root(), maker, taker));
+        assert_eq!(ExchangeModule::fee_rate(), FeeRate { maker, taker });
+
+        //ALICE sells 1000 DOT for 100 BTC
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            1000,
+            BTC,
+            100
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 0));
+
+        assert_eq!(Tokens::free_balance(BTC, &ALICE), ENDOWED_AMOUNT + 100 - 1);
+        assert_eq!(Tokens::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 1000 - 20);
+        assert_eq!(Tokens::free_balance(BTC, &FeeCollector::get()), 1);
+        assert_eq!(Tokens::free_balance(DOT, &FeeCollector::get()), 20);
+    });
+}
+
+#[test]
+fn test_referral() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ExchangeModule::set_referrer(Origin::signed(BOB), BOB),
+            Error::<Test>::InvalidReferrer
+        );
+        assert_ok!(ExchangeModule::set_referrer(Origin::signed(BOB), CHARLIE));
+        assert_noop!(
+            ExchangeModule::set_referrer(Origin::signed(BOB), ALICE),
+            Error::<Test>::ReferrerAlreadySet
+        );
+        assert_eq!(ExchangeModule::referrers(BOB), Some(CHARLIE));
+
+        assert_ok!(ExchangeModule::set_fees(
+            Origin::root(),
+            Permill::from_percent(1),
+            Permill::from_percent(2)
+        ));
+        assert_ok!(ExchangeModule::set_referral_share(
+            Origin::root(),
+            Permill::from_percent(50)
+        ));
+        events();
+
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            1000,
+            BTC,
+            100
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 0));
+
+        //half of BOB's taker fee goes to CHARLIE, ALICE has no referrer
+        assert_eq!(Tokens::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 1000 - 20);
+        assert_eq!(Tokens::free_balance(DOT, &CHARLIE), 10);
+        assert_eq!(Tokens::free_balance(DOT, &FeeCollector::get()), 10);
+        assert_eq!(Tokens::free_balance(BTC, &FeeCollector::get()), 1);
+        assert_eq!(ExchangeModule::referral_earnings(CHARLIE, DOT), 10);
+        assert_eq!(ExchangeModule::referral_earnings(CHARLIE, BTC), 0);
+
+        assert!(events().contains(&Event::pallet_exchange(
+            crate::Event::ReferralRewardPaid(CHARLIE, BOB, DOT, 10)
+        )));
+    });
+}
+
+#[test]
+fn test_fees_below_existential_deposit() {
+    let trade = |builder: ExtBuilder, deltas: &[(AccountId, CurrencyId, Amount)]| {
+        builder
+            .fees(Permill::from_percent(1), Permill::from_percent(2))
+            .build()
+            .execute_with(|| {
+                assert_ok!(ExchangeModule::set_referrer(Origin::signed(ALICE), CHARLIE));
+                assert_ok!(ExchangeModule::set_referral_share(
+                    Origin::root(),
+                    Permill::from_percent(50)
+                ));
+                assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), KSM, 500, DOT, 1000));
+
+                let snapshot = BalanceSnapshot::take(&[ALICE, CHARLIE, FeeCollector::get()], &[KSM]);
+                assert_ok!(ExchangeModule::take_order(Origin::signed(ALICE), 0));
+                snapshot.assert_free_deltas(deltas);
+            })
+    };
+
+    //the 5 KSM of the referral reward would be lost as dust, so the fee collector gets them
+    trade(
+        ExtBuilder::default().balance(FeeCollector::get(), KSM, 1000),
+        &[(ALICE, KSM, 490), (FeeCollector::get(), KSM, 10)],
+    );
+    //and a fee the fee collector can't receive either isn't charged
+    trade(ExtBuilder::default(), &[(ALICE, KSM, 500)]);
+}
+
+#[test]
+fn test_fee_tiers() {
+    new_test_ext().execute_with(|| {
+        let tier = |volume, maker, taker| FeeTier {
+            volume,
+            maker_discount: Permill::from_percent(maker),
+            taker_discount: Permill::from_percent(taker),
+        };
+
+        assert_noop!(
+            ExchangeModule::set_fee_tiers(
+                Origin::root(),
+                DOT,
+                vec![tier(2000, 0, 50), tier(1000, 0, 25)]
+            ),
+            Error::<Test>::InvalidFeeTiers
+        );
+        assert_ok!(ExchangeModule::set_fee_tiers(
+            Origin::root(),
+            DOT,
+            vec![tier(1000, 0, 25), tier(2000, 0, 50)]
+        ));
+        assert_ok!(ExchangeModule::set_fees(
+            Origin::root(),
+            Permill::zero(),
+            Permill::from_percent(2)
+        ));
+
+        let take = |order_id| {
+            assert_ok!(ExchangeModule::submit_order(
+                Origin::signed(ALICE),
+                DOT,
+                1000,
+                BTC,
+                100
+            ));
+            let before = Tokens::free_balance(DOT, &BOB);
+            assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), order_id));
+            1000 - (Tokens::free_balance(DOT, &BOB) - before)
+        };
+
+        //no volume yet, full fee
+        assert_eq!(take(0), 20);
+        assert_eq!(ExchangeModule::rolling_volume(&BOB, DOT), 1000);
+        assert_eq!(ExchangeModule::rolling_volume(&BOB, BTC), 100);
+        assert_eq!(ExchangeModule::rolling_volume(&ALICE, DOT), 1000);
+
+        //first tier
+        System::set_block_number(11);
+        assert_eq!(take(1), 15);
+
+        //second tier, volume is bucketed per day
+        assert_eq!(take(2), 10);
+        assert_eq!(
+            ExchangeModule::trading_volumes(BOB, DOT),
+            vec![(0, 1000), (1, 2000)]
+        );
+
+        //first day drops out of the window
+        System::set_block_number(300);
+        assert_eq!(ExchangeModule::rolling_volume(&BOB, DOT), 2000);
+        assert_eq!(take(3), 10);
+        assert_eq!(
+            ExchangeModule::trading_volumes(BOB, DOT),
+            vec![(1, 2000), (30, 1000)]
+        );
+
+        System::set_block_number(310);
+        assert_eq!(ExchangeModule::rolling_volume(&BOB, DOT), 1000);
+        assert_eq!(take(4), 15);
+
+        //self-trades don't count
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), DOT, 1000, BTC, 100));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 5));
+        assert_eq!(ExchangeModule::rolling_volume(&BOB, DOT), 2000);
+    });
+}
+
+#[test]
+fn test_pause() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            BTC,
+            1
+        ));
+
+        assert_noop!(
+            ExchangeModule::pause(Origin::signed(ALICE)),
+            frame_support::error::BadOrigin
+        );
+        assert_ok!(ExchangeModule::pause(Origin::root()));
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1),
+            Error::<Test>::TradingPaused
+        );
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 0),
+            Error::<Test>::TradingPaused
+        );
+        assert_ok!(ExchangeModule::unpause(Origin::root()));
+
+        //pairs are paused in both directions
+        assert_ok!(ExchangeModule::pause_pair(Origin::root(), BTC, DOT));
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1),
+            Error::<Test>::PairPaused
+        );
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 0),
+            Error::<Test>::PairPaused
+        );
+
+        //orders can always be cancelled
+        assert_ok!(ExchangeModule::cancel_order(Origin::signed(ALICE), 0));
+
+        assert_ok!(ExchangeModule::unpause_pair(Origin::root(), DOT, BTC));
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            BTC,
+            1
+        ));
+    });
+}
+
+#[test]
+fn test_list_pair() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+
+        assert_noop!(
+            ExchangeModule::delist_pair(Origin::signed(ALICE), DOT, BTC),
+            frame_support::error::BadOrigin
+        );
+        assert_ok!(ExchangeModule::delist_pair(Origin::root(), BTC, DOT));
+        assert_noop!(
+            ExchangeModule::delist_pair(Origin::root(), DOT, BTC),
+            Error::<Test>::PairNotListed
+        );
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1),
+            Error::<Test>::PairNotListed
+        );
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 0),
+            Error::<Test>::PairNotListed
+        );
+
+        //orders of delisted pairs can be cancelled
+        assert_ok!(ExchangeModule::cancel_order(Origin::signed(ALICE), 0));
+
+        assert_noop!(
+            ExchangeModule::list_pair(Origin::root(), DOT, DOT),
+            Error::<Test>::InvalidPair
+        );
+        assert_ok!(ExchangeModule::list_pair(Origin::root(), DOT, BTC));
+        assert!(ExchangeModule::listed_pairs((DOT, BTC)));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+
+        let events = events();
+        assert!(events.contains(&Event::pallet_exchange(crate::Event::PairDelisted(DOT, BTC))));
+        assert!(events.contains(&Event::pallet_exchange(crate::Event::PairListed(DOT, BTC))));
+    });
+}
+
+#[test]
+fn test_circuit_breaker() {
+    new_test_ext().execute_with(|| {
+        let price = |n, d| FixedU128::saturating_from_rational(n, d);
+
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            100,
+            BTC,
+            10
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 0));
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(1, 10))
+        );
+
+        //within 10% of the reference price, in the opposite direction
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(BOB),
+            BTC,
+            10,
+            DOT,
+            95
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(ALICE), 1));
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(10, 95))
+        );
+
+        //price doubles, the pair is halted and the order stays on the book
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            100,
+            BTC,
+            20
+        ));
+        events();
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 2));
+        assert!(ExchangeModule::orders(2).is_some());
+        assert_eq!(ExchangeModule::halted_until((DOT, BTC)), Some(6));
+        assert_eq!(
+            events().as_slice(),
+            [Event::pallet_exchange(crate::Event::CircuitBreakerTriggered(
+                DOT,
+                BTC,
+                price(1, 5),
+                6
+            ))]
+        );
+
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 2),
+            Error::<Test>::PairHalted
+        );
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1),
+            Error::<Test>::PairHalted
+        );
+
+        //the halting fill didn't move the reference, so the pair halts again
+        System::set_block_number(6);
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(10, 95))
+        );
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 2));
+        assert!(ExchangeModule::orders(2).is_some());
+        assert_eq!(ExchangeModule::halted_until((DOT, BTC)), Some(11));
+        System::set_block_number(11);
+        assert_ok!(ExchangeModule::cancel_order(Origin::signed(ALICE), 2));
+
+        //self-fills and small fills are ignored
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            100,
+            BTC,
+            50
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(ALICE), 3));
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            100,
+            BTC,
+            9
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 4));
+        assert!(ExchangeModule::orders(3).is_none());
+        assert!(ExchangeModule::orders(4).is_none());
+        assert_eq!(ExchangeModule::halted_until((DOT, BTC)), None);
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(10, 95))
+        );
+
+        //governance resets the reference when unpausing
+        assert_ok!(ExchangeModule::unpause_pair(Origin::root(), BTC, DOT));
+        assert_eq!(ExchangeModule::reference_prices((DOT, BTC)), None);
+    });
+}
+
+#[test]
+fn test_delegated_trading() {
+    ExtBuilder::default()
+        .balance(CHARLIE, BTC, 1000)
+        .build()
+        .execute_with(|| {
+            let permissions = DelegatePermissions {
+                can_submit: true,
+                can_cancel: false,
+                pairs: Some(vec![(BTC, DOT)]),
+                max_notional: Some(vec![(DOT, 100)]),
+            };
+
+            assert_noop!(
+                ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, DOT, 10, BTC, 1),
+                Error::<Test>::NoPermission
+            );
+            assert_noop!(
+                ExchangeModule::authorize_delegate(
+                    Origin::signed(ALICE),
+                    ALICE,
+                    permissions.clone()
+                ),
+                Error::<Test>::InvalidDelegate
+            );
+            assert_ok!(ExchangeModule::authorize_delegate(
+                Origin::signed(ALICE),
+                BOB,
+                permissions.clone()
+            ));
+            //pairs are stored in canonical order
+            assert_eq!(
+                ExchangeModule::delegates(ALICE, BOB).unwrap().pairs,
+                Some(vec![(DOT, BTC)])
+            );
+
+            //funds are reserved from the owner
+            assert_ok!(ExchangeModule::submit_order_for(
+                Origin::signed(BOB),
+                ALICE,
+                DOT,
+                10,
+                BTC,
+                1
+            ));
+            assert_eq!(ExchangeModule::orders(0).unwrap().owner, ALICE);
+            assert_eq!(Tokens::reserved_balance(DOT, &ALICE), 10);
+            assert_eq!(Tokens::free_balance(DOT, &BOB), ENDOWED_AMOUNT);
+
+            assert_eq!(ExchangeModule::delegated_orders(0), Some(BOB));
+
+            //the delegate can't fill the orders it submitted
+            assert_noop!(
+                ExchangeModule::take_order(Origin::signed(BOB), 0),
+                Error::<Test>::NoPermission
+            );
+
+            //the notional limit is cumulative
+            assert_ok!(ExchangeModule::submit_order_for(
+                Origin::signed(BOB),
+                ALICE,
+                DOT,
+                80,
+                BTC,
+                8
+            ));
+            assert_eq!(ExchangeModule::delegated_notional(ALICE, BOB), vec![(DOT, 90)]);
+            assert_noop!(
+                ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, DOT, 11, BTC, 1),
+                Error::<Test>::NoPermission
+            );
+            //and currencies without a limit can't be sold
+            assert_noop!(
+                ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, BTC, 1, DOT, 10),
+                Error::<Test>::NoPermission
+            );
+            assert_noop!(
+                ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, DOT, 10, KSM, 1),
+                Error::<Test>::NoPermission
+            );
+            assert_ok!(ExchangeModule::take_order(Origin::signed(CHARLIE), 1));
+            assert_eq!(ExchangeModule::delegated_orders(1), None);
+
+            //nor have them matched with its own
+            assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 1, DOT, 10));
+            assert_noop!(
+                ExchangeModule::match_orders(Origin::none(), vec![(0, 2)]),
+                Error::<Test>::InvalidMatch
+            );
+
+            //cancelling needs its own permission
+            assert_noop!(
+                ExchangeModule::cancel_order(Origin::signed(BOB), 0),
+                Error::<Test>::NotOwner
+            );
+            assert_ok!(ExchangeModule::authorize_delegate(
+                Origin::signed(ALICE),
+                BOB,
+                DelegatePermissions {
+                    can_cancel: true,
+                    ..permissions
+                }
+            ));
+            assert_ok!(ExchangeModule::cancel_order(Origin::signed(BOB), 0));
+            assert_eq!(ExchangeModule::delegated_orders(0), None);
+            //authorizing again resets the notional
+            assert_eq!(ExchangeModule::delegated_notional(ALICE, BOB), vec![]);
+
+            assert_ok!(ExchangeModule::revoke_delegate(Origin::signed(ALICE), BOB));
+            assert_noop!(
+                ExchangeModule::revoke_delegate(Origin::signed(ALICE), BOB),
+                Error::<Test>::NoPermission
+            );
+            assert_noop!(
+                ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, DOT, 10, BTC, 1),
+                Error::<Test>::NoPermission
+            );
+        });
+}
+
+#[test]
+fn test_minimum_order_size() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 0, BTC, 1),
+            Error::<Test>::OrderTooSmall
+        );
+        assert_noop!(
+            ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 0),
+            Error::<Test>::OrderTooSmall
+        );
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            1,
+            BTC,
+            1
+        ));
+    });
+}
+
+#[test]
+fn test_settlement_leaves_no_dust() {
+    new_test_ext().execute_with(|| {
+        //ALICE would receive less than the existential deposit
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            KSM,
+            50
+        ));
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 0),
+            Error::<Test>::WouldLeaveDust
+        );
+
+        //BOB would be left with less than the existential deposit
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            KSM,
+            950
+        ));
+        assert_noop!(
+            ExchangeModule::take_order(Origin::signed(BOB), 1),
+            Error::<Test>::WouldLeaveDust
+        );
+
+        //emptying an account is fine
+        assert_ok!(ExchangeModule::submit_order(
+            Origin::signed(ALICE),
+            DOT,
+            10,
+            KSM,
+            1000
+        ));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 2));
+        assert_eq!(Tokens::free_balance(KSM, &ALICE), 1000);
+        assert_eq!(Tokens::total_balance(KSM, &BOB), 0);
+    });
+}
+
+#[test]
+fn test_genesis_orders() {
+    ExtBuilder::default()
+        .order(ALICE, DOT, 10, BTC, 1)
+        .order(BOB, KSM, 500, DOT, 5)
+        .build()
+        .execute_with(|| {
+            assert!(ExchangeModule::listed_pairs((DOT, BTC)));
+            assert!(!ExchangeModule::listed_pairs((DOT, DOT)));
+            assert_eq!(
+                ExchangeModule::orders(1),
+                Some(Order {
+                    base_currency_id: KSM,
+                    base_amount: 500,
+                    target_currency_id: DOT,
+                    target_amount: 5,
+                    owner: BOB,
+                    created_at: 0,
+                })
+            );
+            assert_eq!(ExchangeModule::next_orderid(), Some(2));
+            assert_eq!(Tokens::reserved_balance(DOT, &ALICE), 10);
+            assert_eq!(Tokens::reserved_balance(KSM, &BOB), 500);
+
+            assert_ok!(ExchangeModule::take_order(Origin::signed(ALICE), 1));
+            assert_eq!(Tokens::free_balance(KSM, &ALICE), 500);
+        });
+}
+
+#[test]
+fn test_builder_scenario() {
+    ExtBuilder::default()
+        .balance(CHARLIE, DOT, 1000)
+        .balance(ALICE, BTC, 100)
+        .pairs(vec![(DOT, BTC)])
+        .fees(Permill::from_percent(1), Permill::from_percent(2))
+        .build()
+        .execute_with(|| {
+            assert_noop!(
+                ExchangeModule::submit_order(Origin::signed(BOB), DOT, 10, KSM, 1),
+                Error::<Test>::PairNotListed
+            );
+
+            //CHARLIE sells 1000 DOT for 100 BTC
+            let order = Order {
+                base_currency_id: DOT,
+                base_amount: 1000,
+                target_currency_id: BTC,
+                target_amount: 100,
+                owner: CHARLIE,
+                created_at: 1,
+            };
+            let balances =
+                BalanceSnapshot::take(&[ALICE, CHARLIE, FeeCollector::get()], &[DOT, BTC]);
+            assert_ok!(ExchangeModule::submit_order(
+                Origin::signed(CHARLIE),
+                DOT,
+                1000,
+                BTC,
+                100
+            ));
+            assert_ok!(ExchangeModule::take_order(Origin::signed(ALICE), 0));
+
+            balances.assert_free_deltas(&[
+                (ALICE, DOT, 1000 - 20),
+                (ALICE, BTC, -100),
+                (CHARLIE, DOT, -1000),
+                (CHARLIE, BTC, 100 - 1),
+                (FeeCollector::get(), DOT, 20),
+                (FeeCollector::get(), BTC, 1),
+            ]);
+            balances.assert_reserved_deltas(&[]);
+            assert_exchange_events(vec![
+                crate::Event::OrderCreated(0, order.clone()),
+                crate::Event::OrderFilled(Fill {
+                    order_id: 0,
+                    maker: CHARLIE,
+                    taker: ALICE,
+                    base_currency_id: DOT,
+                    target_currency_id: BTC,
+                    base_amount: 1000,
+                    target_amount: 100,
+                    maker_fee: 1,
+                    taker_fee: 20,
+                }),
+                crate::Event::OrderTaken(ALICE, 0, order),
+            ]);
+        });
+}
+
+fn sign(signed_order: &SignedOrderOf<Test>) -> TestSignature {
+    TestSignature(
+        signed_order.order.owner,
+        ExchangeModule::signed_order_payload(signed_order),
+    )
+}
+
+#[test]
+fn test_take_signed_order() {
+    new_test_ext().execute_with(|| {
+        let order = Order {
+            base_currency_id: DOT,
+            base_amount: 10,
+            target_currency_id: BTC,
+            target_amount: 1,
+            owner: ALICE,
+            created_at: 1,
+        };
+        let signed_order = SignedOrder {
+            order: order.clone(),
+            nonce: 7,
+            expiry: 10,
+        };
+
+        //signed by someone else
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                signed_order.clone(),
+                TestSignature(BOB, ExchangeModule::signed_order_payload(&signed_order))
+            ),
+            Error::<Test>::InvalidSignature
+        );
+        //signature of another order
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                SignedOrder {
+                    nonce: 8,
+                    ..signed_order.clone()
+                },
+                sign(&signed_order)
+            ),
+            Error::<Test>::InvalidSignature
+        );
+
+        //signed for another chain
+        let mut payload = SIGNED_ORDER_CONTEXT.to_vec();
+        H256::repeat_byte(1).encode_to(&mut payload);
+        signed_order.encode_to(&mut payload);
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                signed_order.clone(),
+                TestSignature(ALICE, payload)
+            ),
+            Error::<Test>::InvalidSignature
+        );
+
+        assert_ok!(ExchangeModule::take_signed_order(
+            Origin::signed(BOB),
+            signed_order.clone(),
+            sign(&signed_order)
+        ));
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), ENDOWED_AMOUNT - 10);
+        assert_eq!(Tokens::free_balance(BTC, &ALICE), ENDOWED_AMOUNT + 1);
+        assert_eq!(Tokens::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 10);
+        assert_eq!(Tokens::free_balance(BTC, &BOB), ENDOWED_AMOUNT - 1);
+        assert!(ExchangeModule::used_nonces(ALICE, 7));
+        assert_eq!(ExchangeModule::orders(0), None);
+        assert_eq!(ExchangeModule::next_orderid(), Some(1));
+        assert_eq!(order_hooks(), vec![OrderHook::Filled(BOB, 0)]);
+        assert_eq!(
+            events().last(),
+            Some(&Event::pallet_exchange(crate::Event::SignedOrderTaken(
+                BOB, 0, 7, order
+            )))
+        );
+
+        //replayed
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                signed_order.clone(),
+                sign(&signed_order)
+            ),
+            Error::<Test>::NonceUsed
+        );
+    });
+}
+
+#[test]
+fn test_prune_used_nonces() {
+    use frame_support::traits::OnInitialize;
+
+    new_test_ext().execute_with(|| {
+        let signed_order = |nonce, expiry| SignedOrder {
+            order: Order {
+                base_currency_id: DOT,
+                base_amount: 10,
+                target_currency_id: BTC,
+                target_amount: 1,
+                owner: ALICE,
+                created_at: 1,
+            },
+            nonce,
+            expiry,
+        };
+        let take = |signed_order: SignedOrderOf<Test>| {
+            ExchangeModule::take_signed_order(Origin::signed(BOB), signed_order.clone(), sign(&signed_order))
+        };
+
+        assert_ok!(take(signed_order(0, 5)));
+        assert_ok!(ExchangeModule::cancel_signed_order(Origin::signed(ALICE), 1, 6));
+        //cancelling an expired order uses nothing
+        System::set_block_number(3);
+        assert_ok!(ExchangeModule::cancel_signed_order(Origin::signed(ALICE), 2, 2));
+        assert!(!ExchangeModule::used_nonces(ALICE, 2));
+
+        //the nonces are kept while their orders can be taken
+        System::set_block_number(5);
+        ExchangeModule::on_initialize(5);
+        assert_noop!(take(signed_order(0, 5)), Error::<Test>::NonceUsed);
+
+        System::set_block_number(6);
+        ExchangeModule::on_initialize(6);
+        assert!(!ExchangeModule::used_nonces(ALICE, 0));
+        assert!(ExchangeModule::used_nonces(ALICE, 1));
+        assert_noop!(take(signed_order(0, 5)), Error::<Test>::OrderExpired);
+        assert_noop!(take(signed_order(1, 6)), Error::<Test>::NonceUsed);
+
+        System::set_block_number(7);
+        ExchangeModule::on_initialize(7);
+        assert!(!ExchangeModule::used_nonces(ALICE, 1));
+        assert_eq!(NonceExpiries::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn test_signed_order_expiry_and_cancel() {
+    new_test_ext().execute_with(|| {
+        let signed_order = SignedOrder {
+            order: Order {
+                base_currency_id: DOT,
+                base_amount: 10,
+                target_currency_id: BTC,
+                target_amount: 1,
+                owner: ALICE,
+                created_at: 1,
+            },
+            nonce: 0,
+            expiry: 5,
+        };
+
+        System::set_block_number(6);
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                signed_order.clone(),
+                sign(&signed_order)
+            ),
+            Error::<Test>::OrderExpired
+        );
+
+        System::set_block_number(5);
+        assert_ok!(ExchangeModule::cancel_signed_order(Origin::signed(ALICE), 0, 5));
+        assert_eq!(
+            events(),
+            vec![Event::pallet_exchange(crate::Event::SignedOrderCancelled(
+                ALICE, 0
+            ))]
+        );
+        assert_noop!(
+            ExchangeModule::cancel_signed_order(Origin::signed(ALICE), 0, 5),
+            Error::<Test>::NonceUsed
+        );
+        assert_noop!(
+            ExchangeModule::take_signed_order(
+                Origin::signed(BOB),
+                signed_order.clone(),
+                sign(&signed_order)
+            ),
+            Error::<Test>::NonceUsed
+        );
+    });
+}
+
+#[test]
+fn test_match_orders() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 2, DOT, 8));
+        //same side as order 0, doesn't cross order 1
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 5));
+
+        assert_eq!(ExchangeModule::find_matches(), vec![(0, 1)]);
+
+        assert_noop!(
+            ExchangeModule::match_orders(Origin::signed(ALICE), vec![(0, 1)]),
+            frame_support::error::BadOrigin
+        );
+        for matches in vec![vec![], vec![(1, 2)], vec![(0, 0)], vec![(0, 1), (1, 0)], vec![(0, 3)]] {
+            assert_noop!(
+                ExchangeModule::match_orders(Origin::none(), matches),
+                Error::<Test>::InvalidMatch
+            );
+        }
+
+        events();
+        assert_ok!(ExchangeModule::match_orders(Origin::none(), vec![(0, 1)]));
+        //both filled at their own price, the surplus returned to each owner
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), ENDOWED_AMOUNT - 18);
+        assert_eq!(Tokens::reserved_balance(DOT, &ALICE), 10);
+        assert_eq!(Tokens::free_balance(BTC, &ALICE), ENDOWED_AMOUNT + 1);
+        assert_eq!(Tokens::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 8);
+        assert_eq!(Tokens::free_balance(BTC, &BOB), ENDOWED_AMOUNT - 1);
+        assert_eq!(Tokens::reserved_balance(BTC, &BOB), 0);
+        assert_eq!(ExchangeModule::orders(0), None);
+        assert_eq!(ExchangeModule::orders(1), None);
+        assert!(ExchangeModule::orders(2).is_some());
+        assert_eq!(
+            order_hooks()[3..],
+            [OrderHook::Filled(BOB, 0), OrderHook::Filled(ALICE, 1)]
+        );
+        assert_exchange_events(vec![
+            crate::Event::OrderFilled(Fill {
+                order_id: 0,
+                maker: ALICE,
+                taker: BOB,
+                base_currency_id: DOT,
+                target_currency_id: BTC,
+                base_amount: 8,
+                target_amount: 1,
+                maker_fee: 0,
+                taker_fee: 0,
+            }),
+            crate::Event::OrderRefunded(0, ALICE, DOT, 2),
+            crate::Event::OrderFilled(Fill {
+                order_id: 1,
+                maker: BOB,
+                taker: ALICE,
+                base_currency_id: BTC,
+                target_currency_id: DOT,
+                base_amount: 1,
+                target_amount: 8,
+                maker_fee: 0,
+                taker_fee: 0,
+            }),
+            crate::Event::OrderRefunded(1, BOB, BTC, 1),
+            crate::Event::OrdersMatched(0, 1),
+        ]);
+
+        assert_eq!(ExchangeModule::find_matches(), vec![]);
+    });
+}
+
+#[test]
+fn test_match_orders_circuit_breaker() {
+    new_test_ext().execute_with(|| {
+        let price = |n, d| FixedU128::saturating_from_rational(n, d);
+
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 100, BTC, 10));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 10, DOT, 95));
+        assert_ok!(ExchangeModule::match_orders(Origin::none(), vec![(0, 1)]));
+        //the second order of the match sets the reference
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(10, 95))
+        );
+
+        //both orders trade at double the reference price
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 100, BTC, 20));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 20, DOT, 100));
+        events();
+        assert_ok!(ExchangeModule::match_orders(Origin::none(), vec![(2, 3)]));
+        assert!(ExchangeModule::orders(2).is_some());
+        assert!(ExchangeModule::orders(3).is_some());
+        assert_eq!(ExchangeModule::halted_until((DOT, BTC)), Some(6));
+        assert_eq!(
+            ExchangeModule::reference_prices((DOT, BTC)),
+            Some(price(10, 95))
+        );
+        assert_eq!(
+            events().as_slice(),
+            [Event::pallet_exchange(crate::Event::CircuitBreakerTriggered(
+                DOT,
+                BTC,
+                price(1, 5),
+                6
+            ))]
+        );
+    });
+}
+
+#[test]
+fn test_validate_matches() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use frame_support::sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 1, DOT, 10));
+
+        let valid = ExchangeModule::validate_unsigned(
+            TransactionSource::Local,
+            &crate::Call::match_orders(vec![(0, 1)]),
+        )
+        .unwrap();
+        assert_eq!(valid.priority, UnsignedPriority::get());
+        assert_eq!(valid.longevity, MATCH_LONGEVITY);
+
+        assert!(ExchangeModule::validate_unsigned(
+            TransactionSource::InBlock,
+            &crate::Call::match_orders(vec![(0, 1)]),
+        )
+        .is_ok());
+        assert_eq!(
+            ExchangeModule::validate_unsigned(
+                TransactionSource::Local,
+                &crate::Call::match_orders(vec![(0, 2)]),
+            ),
+            InvalidTransaction::Call.into()
+        );
+
+        //only the local off-chain worker and block authors submit matches
+        assert_eq!(
+            ExchangeModule::validate_unsigned(
+                TransactionSource::External,
+                &crate::Call::match_orders(vec![(0, 1)]),
+            ),
+            InvalidTransaction::Call.into()
+        );
+
+        //not valid once the pair is paused
+        assert_ok!(ExchangeModule::pause_pair(Origin::root(), DOT, BTC));
+        assert_eq!(ExchangeModule::find_matches(), vec![]);
+        assert_eq!(
+            ExchangeModule::validate_unsigned(
+                TransactionSource::External,
+                &crate::Call::match_orders(vec![(0, 1)]),
+            ),
+            InvalidTransaction::Call.into()
+        );
+    });
+}
+
+#[test]
+fn test_migrate_v0_orders() {
+    use crate::migrations::v1::{self, OldOrder};
+    use frame_support::traits::OnRuntimeUpgrade;
+
+    new_test_ext().execute_with(|| {
+        assert_eq!(ExchangeModule::storage_version(), Releases::V3);
+        assert!(v1::pre_migrate::<Test>().is_err());
+
+        //fixture of a chain started before storage was versioned or pairs were listed
+        StorageVersion::<Test>::kill();
+        ListedPairs::<Test>::remove((DOT, BTC));
+        let old: OldOrder<CurrencyId, Balance, AccountId> = OldOrder {
+            base_currency_id: DOT,
+            base_amount: 10,
+            target_currency_id: BTC,
+            target_amount: 1,
+            owner: ALICE,
+        };
+        for order_id in 0..3u32 {
+            frame_support::storage::unhashed::put(&Orders::<Test>::hashed_key_for(order_id), &old);
+        }
+        assert_ok!(Tokens::reserve(DOT, &ALICE, 30));
+        NextOrderId::<Test>::put(3);
+        assert_eq!(ExchangeModule::orders(0), None);
+
+        System::set_block_number(5);
+        let checked = crate::migrations::pre_upgrade::<Test>().unwrap();
+        assert_eq!(checked, (Releases::V0, Some(3), Some(0)));
+        ExchangeModule::on_runtime_upgrade();
+        assert_ok!(crate::migrations::post_upgrade::<Test>(checked));
+
+        assert_eq!(ExchangeModule::storage_version(), Releases::V3);
+        assert!(ExchangeModule::listed_pairs((DOT, BTC)));
+        for order_id in 0..3u32 {
+            assert_eq!(
+                ExchangeModule::orders(order_id),
+                Some(Order {
+                    base_currency_id: DOT,
+                    base_amount: 10,
+                    target_currency_id: BTC,
+                    target_amount: 1,
+                    owner: ALICE,
+                    created_at: 5,
+                })
+            );
+        }
+
+        //migrated orders can be traded as usual
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 1));
+
+        //only runs once
+        System::set_block_number(6);
+        ExchangeModule::on_runtime_upgrade();
+        assert_eq!(ExchangeModule::orders(0).map(|order| order.created_at), Some(5));
+    });
+}
+
+#[test]
+fn test_migrate_v1_pairs() {
+    use frame_support::traits::OnRuntimeUpgrade;
+
+    ExtBuilder::default()
+        .order(ALICE, DOT, 10, BTC, 1)
+        .order(BOB, KSM, 500, DOT, 5)
+        .build()
+        .execute_with(|| {
+            //fixture of a chain started before pairs were listed
+            StorageVersion::<Test>::put(Releases::V1);
+            for &(a, b) in [(DOT, BTC), (DOT, KSM), (BTC, KSM)].iter() {
+                ListedPairs::<Test>::remove(ExchangeModule::trading_pair(a, b));
+            }
+            assert!(crate::migrations::v2::post_migrate::<Test>().is_err());
+
+            let checked = crate::migrations::pre_upgrade::<Test>().unwrap();
+            assert_eq!(checked, (Releases::V1, None, Some(0)));
+            ExchangeModule::on_runtime_upgrade();
+            assert_ok!(crate::migrations::post_upgrade::<Test>(checked));
+
+            //only the pairs of open orders are listed
+            assert_eq!(ExchangeModule::storage_version(), Releases::V3);
+            assert!(ExchangeModule::listed_pairs((DOT, BTC)));
+            assert!(ExchangeModule::listed_pairs(ExchangeModule::trading_pair(KSM, DOT)));
+            assert!(!ExchangeModule::listed_pairs(ExchangeModule::trading_pair(BTC, KSM)));
+            assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 0));
+        });
+}
+
+#[test]
+fn test_migrate_v2_delegates() {
+    use crate::migrations::v3::{self, OldDelegatePermissions};
+    use frame_support::traits::OnRuntimeUpgrade;
+
+    new_test_ext().execute_with(|| {
+        assert!(v3::pre_migrate::<Test>().is_err());
+
+        //fixture of a chain whose delegates were limited across currencies
+        StorageVersion::<Test>::put(Releases::V2);
+        let old = |max_notional| OldDelegatePermissions::<CurrencyId, Balance> {
+            can_submit: true,
+            can_cancel: true,
+            pairs: Some(vec![(DOT, BTC)]),
+            max_notional,
+        };
+        frame_support::storage::unhashed::put(&Delegates::<Test>::hashed_key_for(ALICE, BOB), &old(Some(100)));
+        frame_support::storage::unhashed::put(&Delegates::<Test>::hashed_key_for(ALICE, CHARLIE), &old(None));
+        frame_support::storage::unhashed::put(&DelegatedNotional::<Test>::hashed_key_for(ALICE, BOB), &90u128);
+
+        let checked = crate::migrations::pre_upgrade::<Test>().unwrap();
+        assert_eq!(checked, (Releases::V2, None, Some(2)));
+        ExchangeModule::on_runtime_upgrade();
+        assert_ok!(crate::migrations::post_upgrade::<Test>(checked));
+
+        assert_eq!(ExchangeModule::storage_version(), Releases::V3);
+        //the limited delegate has to be authorized again to sell anything
+        assert_eq!(
+            ExchangeModule::delegates(ALICE, BOB),
+            Some(DelegatePermissions {
+                can_submit: true,
+                can_cancel: true,
+                pairs: Some(vec![(DOT, BTC)]),
+                max_notional: Some(vec![]),
+            })
+        );
+        assert_eq!(ExchangeModule::delegates(ALICE, CHARLIE).unwrap().max_notional, None);
+        assert_eq!(ExchangeModule::delegated_notional(ALICE, BOB), vec![]);
+        assert_noop!(
+            ExchangeModule::submit_order_for(Origin::signed(BOB), ALICE, DOT, 10, BTC, 1),
+            Error::<Test>::NoPermission
+        );
+        assert_ok!(ExchangeModule::submit_order_for(Origin::signed(CHARLIE), ALICE, DOT, 10, BTC, 1));
+    });
+}
+
+#[test]
+fn test_try_state() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(ExchangeModule::try_state());
+        assert_eq!(
+            crate::migrations::pre_upgrade::<Test>(),
+            Ok((Releases::V3, None, None))
+        );
+        assert_ok!(crate::migrations::post_upgrade::<Test>((Releases::V3, None, None)));
+
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 20, BTC, 2));
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(BOB), BTC, 2, DOT, 8));
+        assert_ok!(ExchangeModule::take_order(Origin::signed(BOB), 1));
+        assert_ok!(ExchangeModule::match_orders(Origin::none(), vec![(0, 2)]));
+        assert_ok!(ExchangeModule::try_state());
+
+        //reserved balance below the open orders
+        assert_ok!(ExchangeModule::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        Tokens::unreserve(DOT, &ALICE, 1);
+        assert_eq!(
+            ExchangeModule::try_state(),
+            Err("open orders are not covered by reserved balances")
+        );
+        assert_ok!(Tokens::reserve(DOT, &ALICE, 1));
+        assert_ok!(ExchangeModule::try_state());
+
+        //order id not below NextOrderId
+        let order = ExchangeModule::orders(3).unwrap();
+        Orders::<Test>::insert(4, order);
+        assert_eq!(
+            ExchangeModule::try_state(),
+            Err("NextOrderId does not exceed all order ids")
+        );
+    });
+}
@@ -0,0 +1,343 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Paying transaction fees in currencies other than the native one.
+//!
+//! The `ChargeFeeIn` signed extension replaces `pallet_transaction_payment::ChargeTransactionPayment`
+//! in the runtime's `SignedExtra`, and lets the signer pick the currency the fee is paid in. The
+//! fee is computed in the native currency as usual, then converted with the `NativeRate` of the
+//! selected currency and withdrawn from the signer, who is kept alive as with native fees. Fees
+//! paid in other currencies go to `FeeCollector`, native fees go through the runtime's
+//! `OnChargeTransaction`.
+//!
+//! Governance sets the range of rates each currency is converted at with `set_rate_bounds`, and
+//! the `NativeRate` is clamped to it, so a rate moved by a bad price can only go that far. Fees
+//! can't be paid in currencies without bounds, and equal bounds fix the rate.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::DispatchResult,
+	sp_runtime::{
+		helpers_128bit::multiply_by_rational,
+		traits::{
+			CheckedSub, DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SaturatedConversion,
+			Saturating, SignedExtension, UniqueSaturatedInto, Zero,
+		},
+		transaction_validity::{
+			InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError,
+			ValidTransaction,
+		},
+		FixedPointNumber, FixedPointOperand, FixedU128,
+	},
+	traits::Get,
+	weights::{DispatchClass, DispatchInfo, PostDispatchInfo},
+};
+use orml_traits::MultiCurrency;
+use pallet_transaction_payment::OnChargeTransaction;
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Exchange rate between a currency and the native currency.
+pub trait NativeRate<CurrencyId> {
+	/// Amount of `currency_id` worth one unit of the native currency, if known.
+	fn native_rate(currency_id: CurrencyId) -> Option<FixedU128>;
+}
+
+type BalanceOf<T> =
+	<<T as Config>::MultiCurrency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
+type CurrencyIdOf<T> =
+	<<T as Config>::MultiCurrency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Currencies fees can be paid in
+		type MultiCurrency: MultiCurrency<Self::AccountId>;
+		/// Native currency, charged through `pallet_transaction_payment::Config::OnChargeTransaction`
+		#[pallet::constant]
+		type GetNativeCurrencyId: Get<CurrencyIdOf<Self>>;
+		/// Rates used to convert native fees
+		type NativeRate: NativeRate<CurrencyIdOf<Self>>;
+		/// Account receiving the fees paid in non-native currencies
+		type FeeCollector: Get<Self::AccountId>;
+		/// Origin allowed to set the bounds of the rates
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Lowest and highest rate fees are converted at for a currency.
+	#[pallet::storage]
+	#[pallet::getter(fn rate_bounds)]
+	pub type RateBounds<T: Config> =
+		StorageMap<_, Twox64Concat, CurrencyIdOf<T>, (FixedU128, FixedU128)>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", CurrencyIdOf<T> = "CurrencyId", BalanceOf<T> = "Balance")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A fee was paid in a non-native currency. \[who, currency_id, amount\]
+		FeePaid(T::AccountId, CurrencyIdOf<T>, BalanceOf<T>),
+		/// The bounds of the rate of a currency changed. \[currency_id, min, max\]
+		RateBoundsUpdated(CurrencyIdOf<T>, FixedU128, FixedU128),
+		/// Fees can no longer be paid in a currency. \[currency_id\]
+		RateBoundsRemoved(CurrencyIdOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The lower bound is zero or above the upper bound
+		InvalidRateBounds,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Let fees be paid in `currency_id`, converted at its `NativeRate` clamped to
+		/// `min..=max`.
+		#[pallet::weight(10_000)]
+		pub fn set_rate_bounds(
+			origin: OriginFor<T>,
+			currency_id: CurrencyIdOf<T>,
+			min: FixedU128,
+			max: FixedU128,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(!min.is_zero() && min <= max, Error::<T>::InvalidRateBounds);
+			RateBounds::<T>::insert(currency_id, (min, max));
+
+			Self::deposit_event(Event::RateBoundsUpdated(currency_id, min, max));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		pub fn remove_rate_bounds(
+			origin: OriginFor<T>,
+			currency_id: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			RateBounds::<T>::remove(currency_id);
+
+			Self::deposit_event(Event::RateBoundsRemoved(currency_id));
+			Ok(().into())
+		}
+	}
+}
+
+/// Signed extension charging the transaction fee, with the tip, in the currency selected by the
+/// signer, or in the native currency through `pallet_transaction_payment`'s
+/// `OnChargeTransaction` when none or the native one is selected.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeFeeIn<T: Config>(#[codec(compact)] pub BalanceOf<T>, pub Option<CurrencyIdOf<T>>);
+
+/// Native fee handler of the runtime.
+type NativeAdapterOf<T> = <T as pallet_transaction_payment::Config>::OnChargeTransaction;
+
+impl<T: Config> ChargeFeeIn<T>
+where
+	NativeAdapterOf<T>: OnChargeTransaction<T, Balance = BalanceOf<T>>,
+	BalanceOf<T>: FixedPointOperand,
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &T::Call,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> Result<(BalanceOf<T>, Withdrawn<T>), TransactionValidityError> {
+		let tip = self.0;
+		let fee = pallet_transaction_payment::Module::<T>::compute_fee(len as u32, info, tip);
+
+		let currency_id = match self.1 {
+			Some(currency_id) if currency_id != T::GetNativeCurrencyId::get() => currency_id,
+			_ => {
+				return NativeAdapterOf::<T>::withdraw_fee(who, call, info, fee, tip)
+					.map(|imbalance| (fee, Withdrawn::Native(imbalance)))
+			}
+		};
+
+		if fee.is_zero() {
+			return Ok((fee, Withdrawn::Nothing));
+		}
+
+		let (min, max) = Pallet::<T>::rate_bounds(currency_id).ok_or(InvalidTransaction::Payment)?;
+		let rate = T::NativeRate::native_rate(currency_id)
+			.ok_or(InvalidTransaction::Payment)?
+			.max(min)
+			.min(max);
+		let amount = rate
+			.saturating_mul_int(UniqueSaturatedInto::<u128>::unique_saturated_into(fee))
+			.saturated_into::<BalanceOf<T>>();
+
+		// like native fees, paying can't reap the account
+		let remaining = T::MultiCurrency::free_balance(currency_id, who)
+			.checked_sub(&amount)
+			.ok_or(InvalidTransaction::Payment)?;
+		if remaining < T::MultiCurrency::minimum_balance(currency_id) {
+			return Err(InvalidTransaction::Payment.into());
+		}
+		T::MultiCurrency::withdraw(currency_id, who, amount)
+			.map_err(|_| InvalidTransaction::Payment)?;
+
+		Ok((
+			fee,
+			Withdrawn::Other {
+				currency_id,
+				amount,
+				fee,
+			},
+		))
+	}
+
+	/// Same as `ChargeTransactionPayment`: the fee per share of the block the transaction uses.
+	fn get_priority(len: usize, info: &DispatchInfoOf<T::Call>, final_fee: BalanceOf<T>) -> TransactionPriority {
+		let weight_saturation = T::BlockWeights::get().max_block / info.weight.max(1);
+		let max_block_length = *T::BlockLength::get().max.get(DispatchClass::Normal);
+		let len_saturation = max_block_length as u64 / (len as u64).max(1);
+		let coefficient = weight_saturation.min(len_saturation).saturated_into::<BalanceOf<T>>();
+		final_fee.saturating_mul(coefficient).saturated_into::<TransactionPriority>()
+	}
+}
+
+impl<T: Config> sp_std::fmt::Debug for ChargeFeeIn<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeFeeIn({:?}, {:?})", self.0, self.1)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeFeeIn<T>
+where
+	NativeAdapterOf<T>: OnChargeTransaction<T, Balance = BalanceOf<T>>,
+	BalanceOf<T>: Send + Sync + FixedPointOperand,
+	CurrencyIdOf<T>: Send + Sync,
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "ChargeFeeIn";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = (
+		// tip
+		BalanceOf<T>,
+		// who paid the fee
+		Self::AccountId,
+		Withdrawn<T>,
+	);
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let (fee, _) = self.withdraw_fee(who, call, info, len)?;
+		Ok(ValidTransaction {
+			priority: Self::get_priority(len, info, fee),
+			..Default::default()
+		})
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (_, withdrawn) = self.withdraw_fee(who, call, info, len)?;
+		Ok((self.0, who.clone(), withdrawn))
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		_result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let (tip, who, withdrawn) = pre;
+		let corrected_fee =
+			pallet_transaction_payment::Module::<T>::compute_actual_fee(len as u32, info, post_info, tip);
+
+		match withdrawn {
+			Withdrawn::Nothing => Ok(()),
+			Withdrawn::Native(imbalance) => NativeAdapterOf::<T>::correct_and_deposit_fee(
+				&who,
+				info,
+				post_info,
+				corrected_fee,
+				tip,
+				imbalance,
+			),
+			Withdrawn::Other {
+				currency_id,
+				amount,
+				fee,
+			} => {
+				// refund the same share of `amount` as `corrected_fee` is of `fee`
+				let paid = multiply_by_rational(
+					amount.unique_saturated_into(),
+					corrected_fee.min(fee).unique_saturated_into(),
+					fee.unique_saturated_into(),
+				)
+				.map(|x| x.saturated_into::<BalanceOf<T>>())
+				.unwrap_or(amount);
+				let refund = amount.saturating_sub(paid);
+
+				T::MultiCurrency::deposit(currency_id, &who, refund)
+					.map_err(|_| InvalidTransaction::Payment)?;
+				T::MultiCurrency::deposit(currency_id, &T::FeeCollector::get(), paid)
+					.map_err(|_| InvalidTransaction::Payment)?;
+
+				Pallet::<T>::deposit_event(Event::FeePaid(who, currency_id, paid));
+				Ok(())
+			}
+		}
+	}
+}
+
+/// Fee withdrawn by `ChargeFeeIn`, to be corrected after dispatch.
+pub enum Withdrawn<T: Config> {
+	Nothing,
+	Native(<NativeAdapterOf<T> as OnChargeTransaction<T>>::LiquidityInfo),
+	Other {
+		currency_id: CurrencyIdOf<T>,
+		/// Amount withdrawn in `currency_id`
+		amount: BalanceOf<T>,
+		/// Native fee `amount` was converted from
+		fee: BalanceOf<T>,
+	},
+}
+
+impl<T: Config> Default for Withdrawn<T> {
+	fn default() -> Self {
+		Withdrawn::Nothing
+	}
+}
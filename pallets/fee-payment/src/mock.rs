@@ -0,0 +1,179 @@
+use crate as pallet_fee_payment;
+use crate::NativeRate;
+use frame_support::parameter_types;
+use frame_support::sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup, Zero},
+    FixedPointNumber, FixedU128,
+};
+use frame_support::weights::IdentityFee;
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+
+use orml_traits::parameter_type_with_key;
+
+use frame_support::traits::GenesisBuild;
+
+pub type Amount = i128;
+pub type AccountId = u64;
+pub type Balance = u128;
+pub type CurrencyId = u128;
+pub type BlockNumber = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const FEE_COLLECTOR: AccountId = 99;
+
+pub const NATIVE: CurrencyId = 0;
+pub const DOT: CurrencyId = 1;
+pub const BTC: CurrencyId = 3;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Module, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+        Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
+        TransactionPayment: pallet_transaction_payment::{Module, Storage},
+        FeePayment: pallet_fee_payment::{Module, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_type_with_key! {
+    pub ExistentialDeposits: |currency_id: CurrencyId| -> Balance {
+        match *currency_id {
+            DOT => 10,
+            _ => Zero::zero(),
+        }
+    };
+}
+
+impl orml_tokens::Config for Test {
+    type Event = Event;
+    type Balance = Balance;
+    type Amount = Amount;
+    type CurrencyId = CurrencyId;
+    type WeightInfo = ();
+    type ExistentialDeposits = ExistentialDeposits;
+    type OnDust = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 1;
+    pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = MaxLocks;
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const TransactionByteFee: Balance = 1;
+}
+
+impl pallet_transaction_payment::Config for Test {
+    type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, ()>;
+    type TransactionByteFee = TransactionByteFee;
+    type WeightToFee = IdentityFee<Balance>;
+    type FeeMultiplierUpdate = ();
+}
+
+/// Two DOT per native unit, no rate for anything else.
+pub struct MockNativeRate;
+
+impl NativeRate<CurrencyId> for MockNativeRate {
+    fn native_rate(currency_id: CurrencyId) -> Option<FixedU128> {
+        match currency_id {
+            DOT => Some(FixedU128::saturating_from_integer(2)),
+            _ => None,
+        }
+    }
+}
+
+parameter_types! {
+    pub const GetNativeCurrencyId: CurrencyId = NATIVE;
+    pub const FeeCollector: AccountId = FEE_COLLECTOR;
+}
+
+impl pallet_fee_payment::Config for Test {
+    type Event = Event;
+    type MultiCurrency = Tokens;
+    type GetNativeCurrencyId = GetNativeCurrencyId;
+    type NativeRate = MockNativeRate;
+    type FeeCollector = FeeCollector;
+    type UpdateOrigin = EnsureRoot<AccountId>;
+}
+
+pub const INITIAL_BALANCE: Balance = 1_000_000_000_000;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(ALICE, INITIAL_BALANCE)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    orml_tokens::GenesisConfig::<Test> {
+        endowed_accounts: vec![(ALICE, DOT, INITIAL_BALANCE), (ALICE, BTC, INITIAL_BALANCE)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        // DOT is converted at its rate
+        pallet_fee_payment::RateBounds::<Test>::insert(
+            DOT,
+            (FixedU128::saturating_from_integer(1), FixedU128::saturating_from_integer(3)),
+        );
+    });
+    ext
+}
@@ -0,0 +1,149 @@
+use super::*;
+use crate::mock::{Call, Event, *};
+use frame_support::weights::{DispatchInfo, PostDispatchInfo, Weight};
+use frame_support::{assert_noop, assert_ok};
+use orml_traits::MultiCurrency;
+
+const CALL: &<Test as frame_system::Config>::Call = &Call::System(frame_system::Call::remark(vec![]));
+const LEN: usize = 10;
+
+fn info(weight: Weight) -> DispatchInfo {
+    DispatchInfo {
+        weight,
+        ..Default::default()
+    }
+}
+
+fn post_info(weight: Weight) -> PostDispatchInfo {
+    PostDispatchInfo {
+        actual_weight: Some(weight),
+        pays_fee: Default::default(),
+    }
+}
+
+/// Charges `who` around a call of `weight` whose actual weight is `actual_weight`.
+fn charge_from(
+    who: AccountId,
+    currency_id: Option<CurrencyId>,
+    weight: Weight,
+    actual_weight: Weight,
+) -> Result<(), TransactionValidityError> {
+    let pre = ChargeFeeIn::<Test>(0, currency_id).pre_dispatch(&who, CALL, &info(weight), LEN)?;
+    ChargeFeeIn::<Test>::post_dispatch(pre, &info(weight), &post_info(actual_weight), LEN, &Ok(()))
+}
+
+fn charge(
+    currency_id: Option<CurrencyId>,
+    weight: Weight,
+    actual_weight: Weight,
+) -> Result<(), TransactionValidityError> {
+    charge_from(ALICE, currency_id, weight, actual_weight)
+}
+
+#[test]
+fn test_native_fee() {
+    new_test_ext().execute_with(|| {
+        let fee = TransactionPayment::compute_actual_fee(LEN as u32, &info(1000), &post_info(500), 0);
+
+        assert!(charge(None, 1000, 500).is_ok());
+        assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - fee);
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), INITIAL_BALANCE);
+
+        // selecting the native currency explicitly is the same
+        assert!(charge(Some(NATIVE), 1000, 500).is_ok());
+        assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - 2 * fee);
+    });
+}
+
+#[test]
+fn test_fee_in_other_currency() {
+    new_test_ext().execute_with(|| {
+        let fee = TransactionPayment::compute_actual_fee(LEN as u32, &info(1000), &post_info(500), 0);
+        assert!(fee < TransactionPayment::compute_fee(LEN as u32, &info(1000), 0));
+
+        assert!(charge(Some(DOT), 1000, 500).is_ok());
+
+        // the unused part of the weight is refunded
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), INITIAL_BALANCE - 2 * fee);
+        assert_eq!(Tokens::free_balance(DOT, &FEE_COLLECTOR), 2 * fee);
+        assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+
+        assert!(System::events()
+            .into_iter()
+            .any(|record| record.event == Event::pallet_fee_payment(crate::Event::FeePaid(ALICE, DOT, 2 * fee))));
+    });
+}
+
+#[test]
+fn test_fee_keeps_account_alive() {
+    new_test_ext().execute_with(|| {
+        let fee = TransactionPayment::compute_fee(LEN as u32, &info(1000), 0);
+
+        // paying would leave less than the existential deposit of DOT
+        assert_ok!(Tokens::deposit(DOT, &BOB, 2 * fee + 9));
+        assert_eq!(
+            charge_from(BOB, Some(DOT), 1000, 1000),
+            Err(InvalidTransaction::Payment.into())
+        );
+        assert_eq!(Tokens::free_balance(DOT, &BOB), 2 * fee + 9);
+
+        assert_ok!(Tokens::deposit(DOT, &BOB, 1));
+        assert!(charge_from(BOB, Some(DOT), 1000, 1000).is_ok());
+        assert_eq!(Tokens::free_balance(DOT, &BOB), 10);
+    });
+}
+
+#[test]
+fn test_fee_without_rate() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            charge(Some(BTC), 1000, 500),
+            Err(InvalidTransaction::Payment.into())
+        );
+        assert_eq!(Tokens::free_balance(BTC, &ALICE), INITIAL_BALANCE);
+        assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn test_rate_bounds() {
+    new_test_ext().execute_with(|| {
+        let rate = FixedU128::saturating_from_integer;
+        let fee = TransactionPayment::compute_actual_fee(LEN as u32, &info(1000), &post_info(1000), 0);
+
+        assert_noop!(
+            FeePayment::set_rate_bounds(Origin::signed(ALICE), DOT, rate(3), rate(4)),
+            frame_support::error::BadOrigin
+        );
+        assert_noop!(
+            FeePayment::set_rate_bounds(Origin::root(), DOT, rate(0), rate(4)),
+            Error::<Test>::InvalidRateBounds
+        );
+        assert_noop!(
+            FeePayment::set_rate_bounds(Origin::root(), DOT, rate(4), rate(3)),
+            Error::<Test>::InvalidRateBounds
+        );
+
+        // the rate of 2 is raised to the lower bound
+        assert_ok!(FeePayment::set_rate_bounds(Origin::root(), DOT, rate(3), rate(4)));
+        assert_eq!(FeePayment::rate_bounds(DOT), Some((rate(3), rate(4))));
+        assert!(charge(Some(DOT), 1000, 1000).is_ok());
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), INITIAL_BALANCE - 3 * fee);
+
+        // and lowered to the upper bound
+        assert_ok!(FeePayment::set_rate_bounds(Origin::root(), DOT, rate(1), rate(1)));
+        assert!(charge(Some(DOT), 1000, 1000).is_ok());
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), INITIAL_BALANCE - 4 * fee);
+
+        // currencies without bounds can't pay fees
+        assert_ok!(FeePayment::remove_rate_bounds(Origin::root(), DOT));
+        assert_eq!(
+            charge(Some(DOT), 1000, 1000),
+            Err(InvalidTransaction::Payment.into())
+        );
+        assert_eq!(Tokens::free_balance(DOT, &ALICE), INITIAL_BALANCE - 4 * fee);
+
+        assert!(System::events().into_iter().any(|record| record.event
+            == Event::pallet_fee_payment(crate::Event::RateBoundsUpdated(DOT, rate(1), rate(1)))));
+    });
+}
@@ -0,0 +1,248 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Liquidity mining for the exchange.
+//!
+//! Every time an order is taken, the maker is credited with the volume of the fill, counted in the
+//! first currency of its `pallet_exchange::trading_pair`, whichever side of the pair the order
+//! sold. Fills of an order by its own owner earn nothing. Governance configures how much of the
+//! native currency is paid out per pair for each reward period, and makers claim their pro-rata
+//! share of a finished period with `claim_rewards`.
+//!
+//! All work is done either in the fill hook or in `claim_rewards`, and both are `O(1)`, so the
+//! per-block cost does not depend on the number of makers.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	sp_runtime::{
+		helpers_128bit::multiply_by_rational,
+		traits::{AccountIdConversion, Saturating, UniqueSaturatedInto, Zero},
+		ModuleId, RuntimeDebug, SaturatedConversion,
+	},
+	traits::Get,
+	transactional,
+};
+use orml_traits::MultiCurrency;
+use pallet_exchange::{trading_pair, OnOrderFilled, Order};
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Rewards of a pair for one period, snapshotted on the first fill of the period.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct PeriodReward<Balance> {
+	/// Native currency not yet claimed for this period
+	pub reward: Balance,
+	/// Maker volume not yet claimed for this period
+	pub volume: Balance,
+}
+
+type BalanceOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
+type CurrencyIdOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
+type PairOf<T> = (CurrencyIdOf<T>, CurrencyIdOf<T>);
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Currency
+		type Currency: MultiCurrency<Self::AccountId>;
+		/// Currency the rewards are paid in
+		#[pallet::constant]
+		type GetNativeCurrencyId: Get<CurrencyIdOf<Self>>;
+		/// Length of a reward period in blocks
+		#[pallet::constant]
+		type RewardPeriod: Get<Self::BlockNumber>;
+		/// Origin allowed to configure the reward amounts
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+		/// Id of the account holding the rewards to be paid
+		#[pallet::constant]
+		type ModuleId: Get<ModuleId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Native currency distributed per period to the makers of a pair, see `trading_pair`.
+	#[pallet::storage]
+	#[pallet::getter(fn reward_amounts)]
+	pub type RewardAmounts<T: Config> =
+		StorageMap<_, Twox64Concat, PairOf<T>, BalanceOf<T>, ValueQuery>;
+
+	/// Unclaimed rewards and volume of a pair in a period.
+	#[pallet::storage]
+	#[pallet::getter(fn period_rewards)]
+	pub type PeriodRewards<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Twox64Concat,
+		PairOf<T>,
+		PeriodReward<BalanceOf<T>>,
+	>;
+
+	/// Unclaimed maker volume of an account for a pair in a period.
+	#[pallet::storage]
+	#[pallet::getter(fn maker_volumes)]
+	pub type MakerVolumes<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		(T::BlockNumber, PairOf<T>),
+		Twox64Concat,
+		T::AccountId,
+		BalanceOf<T>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		T::BlockNumber = "BlockNumber",
+		CurrencyIdOf<T> = "CurrencyId",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The reward paid per period for a pair changed. \[currency_id_0, currency_id_1, amount\]
+		RewardAmountUpdated(CurrencyIdOf<T>, CurrencyIdOf<T>, BalanceOf<T>),
+		/// A maker claimed rewards. \[who, period, currency_id_0, currency_id_1, amount\]
+		RewardsClaimed(T::AccountId, T::BlockNumber, CurrencyIdOf<T>, CurrencyIdOf<T>, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		PeriodNotFinished,
+		NoRewards,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(10_000)]
+		pub fn set_reward_amount(
+			origin: OriginFor<T>,
+			base_currency_id: CurrencyIdOf<T>,
+			target_currency_id: CurrencyIdOf<T>,
+			amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let pair = trading_pair(base_currency_id, target_currency_id);
+			RewardAmounts::<T>::insert(pair, amount);
+
+			Self::deposit_event(Event::RewardAmountUpdated(pair.0, pair.1, amount));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		#[transactional]
+		pub fn claim_rewards(
+			origin: OriginFor<T>,
+			period: T::BlockNumber,
+			base_currency_id: CurrencyIdOf<T>,
+			target_currency_id: CurrencyIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(period < Self::current_period(), Error::<T>::PeriodNotFinished);
+
+			let pair = trading_pair(base_currency_id, target_currency_id);
+			let volume = MakerVolumes::<T>::take((period, pair), &who);
+			ensure!(!volume.is_zero(), Error::<T>::NoRewards);
+
+			let amount = PeriodRewards::<T>::try_mutate_exists(
+				period,
+				pair,
+				|info| -> Result<BalanceOf<T>, DispatchError> {
+					let mut rewards = info.take().ok_or(Error::<T>::NoRewards)?;
+
+					// the last claimer receives whatever is left, so no dust stays behind
+					let amount = multiply_by_rational(
+						rewards.reward.unique_saturated_into(),
+						volume.unique_saturated_into(),
+						rewards.volume.unique_saturated_into(),
+					)
+					.map(|x| x.saturated_into::<BalanceOf<T>>())
+					.unwrap_or(rewards.reward)
+					.min(rewards.reward);
+
+					rewards.reward = rewards.reward.saturating_sub(amount);
+					rewards.volume = rewards.volume.saturating_sub(volume);
+					if !rewards.volume.is_zero() {
+						*info = Some(rewards);
+					}
+					Ok(amount)
+				},
+			)?;
+
+			T::Currency::transfer(T::GetNativeCurrencyId::get(), &Self::account_id(), &who, amount)?;
+
+			Self::deposit_event(Event::RewardsClaimed(who, period, pair.0, pair.1, amount));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Account holding the rewards to be paid.
+	pub fn account_id() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// Index of the reward period the current block belongs to.
+	pub fn current_period() -> T::BlockNumber {
+		frame_system::Module::<T>::block_number() / T::RewardPeriod::get()
+	}
+}
+
+impl<T: Config, OrderId> OnOrderFilled<T::AccountId, OrderId, Order<CurrencyIdOf<T>, BalanceOf<T>, T::AccountId, T::BlockNumber>>
+	for Pallet<T>
+{
+	fn on_order_filled(
+		taker: &T::AccountId,
+		_order_id: &OrderId,
+		order: &Order<CurrencyIdOf<T>, BalanceOf<T>, T::AccountId, T::BlockNumber>,
+	) {
+		// self-fills would let makers farm rewards with their own funds
+		if *taker == order.owner {
+			return;
+		}
+
+		let pair = trading_pair(order.base_currency_id, order.target_currency_id);
+		let reward = RewardAmounts::<T>::get(pair);
+		let volume = if order.base_currency_id == pair.0 {
+			order.base_amount
+		} else {
+			order.target_amount
+		};
+		if reward.is_zero() || volume.is_zero() {
+			return;
+		}
+
+		let period = Self::current_period();
+		PeriodRewards::<T>::mutate(period, pair, |info| {
+			let info = info.get_or_insert_with(|| PeriodReward {
+				reward,
+				volume: Zero::zero(),
+			});
+			info.volume = info.volume.saturating_add(volume);
+		});
+		MakerVolumes::<T>::mutate((period, pair), &order.owner, |maker_volume| {
+			*maker_volume = maker_volume.saturating_add(volume)
+		});
+	}
+}
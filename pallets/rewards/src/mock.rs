@@ -0,0 +1,239 @@
+use crate as pallet_rewards;
+use frame_support::parameter_types;
+use frame_support::sp_runtime::{
+    testing::{Header, TestSignature, UintAuthorityId},
+    traits::{AccountIdConversion, BlakeTwo256, IdentityLookup, Zero},
+    ModuleId, Permill,
+};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+
+use orml_traits::parameter_type_with_key;
+
+use frame_support::traits::GenesisBuild;
+
+pub type Amount = i128;
+pub type AccountId = u64;
+pub type Balance = u128;
+pub type CurrencyId = u128;
+pub type BlockNumber = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+
+pub const NATIVE: CurrencyId = 0;
+pub const DOT: CurrencyId = 1;
+pub const BTC: CurrencyId = 3;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Module, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+        Currencies: orml_currencies::{Module, Call, Event<T>},
+        Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
+        Exchange: pallet_exchange::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
+        Rewards: pallet_rewards::{Module, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u128>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+}
+
+parameter_type_with_key! {
+    pub ExistentialDeposits: |_currency_id: CurrencyId| -> Balance {
+        Zero::zero()
+    };
+}
+
+impl orml_tokens::Config for Test {
+    type Event = Event;
+    type Balance = Balance;
+    type Amount = Amount;
+    type CurrencyId = CurrencyId;
+    type WeightInfo = ();
+    type ExistentialDeposits = ExistentialDeposits;
+    type OnDust = ();
+}
+
+parameter_types! {
+    pub const GetNativeCurrencyId: CurrencyId = NATIVE;
+}
+
+impl orml_currencies::Config for Test {
+    type Event = Event;
+    type MultiCurrency = Tokens;
+    type NativeCurrency =
+        orml_currencies::BasicCurrencyAdapter<Test, Balances, Amount, BlockNumber>;
+    type GetNativeCurrencyId = GetNativeCurrencyId;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u128 = 1;
+    pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = MaxLocks;
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_type_with_key! {
+    pub MinimumOrderSizes: |_currency_id: CurrencyId| -> Balance {
+        1
+    };
+}
+
+parameter_type_with_key! {
+    pub CircuitBreakerMinimumSizes: |_currency_id: CurrencyId| -> Balance {
+        10
+    };
+}
+
+parameter_types! {
+    pub const FeeCollector: AccountId = 99;
+    pub const BlocksPerDay: BlockNumber = 10;
+    pub const CircuitBreakerThreshold: Permill = Permill::from_percent(10);
+    pub const CircuitBreakerDuration: BlockNumber = 5;
+    pub const UnsignedPriority: u64 = 1 << 20;
+}
+
+impl pallet_exchange::Config for Test {
+    type Event = Event;
+    type Currency = Currencies;
+    type OrderId = u32;
+    type OnOrderCreated = ();
+    type OnOrderFilled = Rewards;
+    type OnOrderCancelled = ();
+    type UpdateOrigin = EnsureRoot<AccountId>;
+    type FeeCollector = FeeCollector;
+    type BlocksPerDay = BlocksPerDay;
+    type PauseOrigin = EnsureRoot<AccountId>;
+    type CircuitBreakerThreshold = CircuitBreakerThreshold;
+    type CircuitBreakerDuration = CircuitBreakerDuration;
+    type MinimumOrderSize = MinimumOrderSizes;
+    type CircuitBreakerMinimumSize = CircuitBreakerMinimumSizes;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
+    type UnsignedPriority = UnsignedPriority;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+    Call: From<C>,
+{
+    type OverarchingCall = Call;
+    type Extrinsic = UncheckedExtrinsic;
+}
+
+parameter_types! {
+    pub const RewardPeriod: BlockNumber = 10;
+    pub const RewardsModuleId: ModuleId = ModuleId(*b"orml/rwd");
+}
+
+impl pallet_rewards::Config for Test {
+    type Event = Event;
+    type Currency = Currencies;
+    type GetNativeCurrencyId = GetNativeCurrencyId;
+    type RewardPeriod = RewardPeriod;
+    type UpdateOrigin = EnsureRoot<AccountId>;
+    type ModuleId = RewardsModuleId;
+}
+
+pub fn rewards_account() -> AccountId {
+    RewardsModuleId::get().into_account()
+}
+
+pub struct ExtBuilder {
+    endowed_accounts: Vec<(AccountId, CurrencyId, Balance)>,
+    reward_pot: Balance,
+}
+
+impl Default for ExtBuilder {
+    fn default() -> Self {
+        Self {
+            endowed_accounts: vec![
+                (ALICE, DOT, 1_000_000),
+                (BOB, DOT, 1_000_000),
+                (CHARLIE, DOT, 1_000_000),
+                (ALICE, BTC, 1_000_000),
+                (BOB, BTC, 1_000_000),
+                (CHARLIE, BTC, 1_000_000),
+            ],
+            reward_pot: 1_000_000,
+        }
+    }
+}
+
+impl ExtBuilder {
+    pub fn build(self) -> sp_io::TestExternalities {
+        let mut t = frame_system::GenesisConfig::default()
+            .build_storage::<Test>()
+            .unwrap();
+
+        pallet_balances::GenesisConfig::<Test> {
+            balances: vec![(rewards_account(), self.reward_pot)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        orml_tokens::GenesisConfig::<Test> {
+            endowed_accounts: self.endowed_accounts,
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        pallet_exchange::GenesisConfig::<Test> {
+            listed_pairs: vec![(DOT, BTC)],
+            fee_rate: Default::default(),
+            orders: vec![],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        let mut ext: sp_io::TestExternalities = t.into();
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+use crate::mock::{Event, *};
+use frame_support::{assert_noop, assert_ok, traits::Currency};
+
+fn events() -> Vec<Event> {
+    let evt = System::events()
+        .into_iter()
+        .map(|evt| evt.event)
+        .collect::<Vec<_>>();
+    System::reset_events();
+    evt
+}
+
+#[test]
+fn test_set_reward_amount() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_noop!(
+            Rewards::set_reward_amount(Origin::signed(ALICE), DOT, BTC, 1000),
+            frame_support::error::BadOrigin
+        );
+
+        //pairs are unordered
+        assert_ok!(Rewards::set_reward_amount(Origin::root(), BTC, DOT, 1000));
+        assert_eq!(Rewards::reward_amounts((DOT, BTC)), 1000);
+        assert_eq!(Rewards::reward_amounts((BTC, DOT)), 0);
+
+        assert_eq!(
+            events().as_slice(),
+            [Event::pallet_rewards(crate::Event::RewardAmountUpdated(DOT, BTC, 1000))]
+        );
+    });
+}
+
+#[test]
+fn test_claim_rewards() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Rewards::set_reward_amount(Origin::root(), DOT, BTC, 1000));
+
+        //ALICE makes 10 + 10, BOB makes 20, CHARLIE takes all, volume counted in DOT
+        assert_ok!(Exchange::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(Exchange::submit_order(Origin::signed(BOB), DOT, 20, BTC, 2));
+        assert_ok!(Exchange::submit_order(Origin::signed(ALICE), BTC, 1, DOT, 10));
+        assert_ok!(Exchange::take_order(Origin::signed(CHARLIE), 0));
+        assert_ok!(Exchange::take_order(Origin::signed(CHARLIE), 1));
+        assert_ok!(Exchange::take_order(Origin::signed(CHARLIE), 2));
+
+        assert_eq!(
+            Rewards::period_rewards(0, (DOT, BTC)),
+            Some(PeriodReward { reward: 1000, volume: 40 })
+        );
+        assert_eq!(Rewards::maker_volumes((0, (DOT, BTC)), ALICE), 20);
+        assert_eq!(Rewards::maker_volumes((0, (DOT, BTC)), BOB), 20);
+        assert_eq!(Rewards::period_rewards(0, (BTC, DOT)), None);
+
+        assert_noop!(
+            Rewards::claim_rewards(Origin::signed(ALICE), 0, DOT, BTC),
+            Error::<Test>::PeriodNotFinished
+        );
+
+        System::set_block_number(10);
+
+        assert_noop!(
+            Rewards::claim_rewards(Origin::signed(CHARLIE), 0, DOT, BTC),
+            Error::<Test>::NoRewards
+        );
+
+        assert_ok!(Rewards::claim_rewards(Origin::signed(ALICE), 0, BTC, DOT));
+        assert_eq!(Balances::free_balance(ALICE), 500);
+        assert_noop!(
+            Rewards::claim_rewards(Origin::signed(ALICE), 0, DOT, BTC),
+            Error::<Test>::NoRewards
+        );
+
+        assert_ok!(Rewards::claim_rewards(Origin::signed(BOB), 0, DOT, BTC));
+        assert_eq!(Balances::free_balance(BOB), 500);
+        assert_eq!(Balances::free_balance(rewards_account()), 1_000_000 - 1000);
+
+        //fully claimed periods are cleaned up
+        assert_eq!(Rewards::period_rewards(0, (DOT, BTC)), None);
+    });
+}
+
+#[test]
+fn test_self_fills_earn_nothing() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Rewards::set_reward_amount(Origin::root(), DOT, BTC, 1000));
+
+        assert_ok!(Exchange::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(Exchange::take_order(Origin::signed(ALICE), 0));
+
+        assert_eq!(Rewards::period_rewards(0, (DOT, BTC)), None);
+        assert_eq!(Rewards::maker_volumes((0, (DOT, BTC)), ALICE), 0);
+    });
+}
+
+#[test]
+fn test_failed_claim_keeps_volume() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Rewards::set_reward_amount(Origin::root(), DOT, BTC, 1000));
+        assert_ok!(Exchange::submit_order(Origin::signed(ALICE), DOT, 10, BTC, 1));
+        assert_ok!(Exchange::take_order(Origin::signed(BOB), 0));
+        System::set_block_number(10);
+
+        //the pot can't pay
+        Balances::make_free_balance_be(&rewards_account(), 0);
+        assert!(Rewards::claim_rewards(Origin::signed(ALICE), 0, DOT, BTC).is_err());
+        assert_eq!(Rewards::maker_volumes((0, (DOT, BTC)), ALICE), 10);
+        assert_eq!(
+            Rewards::period_rewards(0, (DOT, BTC)),
+            Some(PeriodReward { reward: 1000, volume: 10 })
+        );
+
+        Balances::make_free_balance_be(&rewards_account(), 1000);
+        assert_ok!(Rewards::claim_rewards(Origin::signed(ALICE), 0, DOT, BTC));
+        assert_eq!(Balances::free_balance(ALICE), 1000);
+    });
+}
@@ -0,0 +1,208 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Multi-currency treasury.
+//!
+//! The treasury is a plain account derived from `ModuleId`, so transaction fees, exchange fees
+//! and dust of any currency are routed into it by pointing their collectors at `account_id`.
+//!
+//! Anyone can propose to pay some of the treasury funds to a beneficiary by reserving
+//! `ProposalBond` of the native currency. `ApproveOrigin` then either approves the proposal,
+//! paying the beneficiary and returning the bond, or rejects it, moving the bond to the treasury.
+
+use codec::{Decode, Encode};
+use frame_support::sp_runtime::{
+	traits::{AccountIdConversion, Saturating},
+	ModuleId, RuntimeDebug,
+};
+use frame_support::traits::{BalanceStatus, Get};
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub type ProposalIndex = u32;
+
+/// A proposed payment out of the treasury.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SpendProposal<AccountId, CurrencyId, Balance> {
+	pub proposer: AccountId,
+	pub beneficiary: AccountId,
+	pub currency_id: CurrencyId,
+	pub amount: Balance,
+	/// Native currency reserved from the proposer
+	pub bond: Balance,
+}
+
+type BalanceOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::Balance;
+type CurrencyIdOf<T> =
+	<<T as Config>::Currency as MultiCurrency<<T as frame_system::Config>::AccountId>>::CurrencyId;
+type SpendProposalOf<T> =
+	SpendProposal<<T as frame_system::Config>::AccountId, CurrencyIdOf<T>, BalanceOf<T>>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+		/// Currency
+		type Currency: MultiReservableCurrency<Self::AccountId>;
+		/// Currency the proposal bond is paid in
+		#[pallet::constant]
+		type GetNativeCurrencyId: Get<CurrencyIdOf<Self>>;
+		/// Amount reserved from the proposer of a spend
+		#[pallet::constant]
+		type ProposalBond: Get<BalanceOf<Self>>;
+		/// Origin allowed to approve or reject spend proposals
+		type ApproveOrigin: EnsureOrigin<Self::Origin>;
+		/// Id of the treasury account
+		#[pallet::constant]
+		type ModuleId: Get<ModuleId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	#[pallet::getter(fn proposals)]
+	pub type Proposals<T: Config> = StorageMap<_, Twox64Concat, ProposalIndex, SpendProposalOf<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn proposal_count)]
+	pub type ProposalCount<T: Config> = StorageValue<_, ProposalIndex, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		CurrencyIdOf<T> = "CurrencyId",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A spend was proposed. \[proposal_index, proposer, beneficiary, currency_id, amount\]
+		Proposed(ProposalIndex, T::AccountId, T::AccountId, CurrencyIdOf<T>, BalanceOf<T>),
+		/// A proposal was approved and paid. \[proposal_index, beneficiary, currency_id, amount\]
+		Awarded(ProposalIndex, T::AccountId, CurrencyIdOf<T>, BalanceOf<T>),
+		/// A proposal was rejected and what is left of its bond moved to the treasury.
+		/// \[proposal_index, bond\]
+		Rejected(ProposalIndex, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		ProposalIndexOverflow,
+		InvalidProposalIndex,
+		InsufficientProposersBalance,
+		InsufficientTreasuryBalance,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Propose to pay `amount` of `currency_id` to `beneficiary`, reserving `ProposalBond`.
+		#[pallet::weight(10_000)]
+		#[frame_support::transactional]
+		pub fn propose_spend(
+			origin: OriginFor<T>,
+			currency_id: CurrencyIdOf<T>,
+			amount: BalanceOf<T>,
+			beneficiary: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			let proposer = ensure_signed(origin)?;
+
+			let bond = T::ProposalBond::get();
+			T::Currency::reserve(T::GetNativeCurrencyId::get(), &proposer, bond)
+				.map_err(|_| Error::<T>::InsufficientProposersBalance)?;
+
+			let index = ProposalCount::<T>::try_mutate(|count| -> Result<ProposalIndex, DispatchError> {
+				let index = *count;
+				*count = count.checked_add(1).ok_or(Error::<T>::ProposalIndexOverflow)?;
+				Ok(index)
+			})?;
+
+			Proposals::<T>::insert(
+				index,
+				SpendProposal {
+					proposer: proposer.clone(),
+					beneficiary: beneficiary.clone(),
+					currency_id,
+					amount,
+					bond,
+				},
+			);
+
+			Self::deposit_event(Event::Proposed(index, proposer, beneficiary, currency_id, amount));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		#[frame_support::transactional]
+		pub fn approve_proposal(origin: OriginFor<T>, proposal_index: ProposalIndex) -> DispatchResultWithPostInfo {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let proposal = Proposals::<T>::take(proposal_index).ok_or(Error::<T>::InvalidProposalIndex)?;
+
+			T::Currency::transfer(
+				proposal.currency_id,
+				&Self::account_id(),
+				&proposal.beneficiary,
+				proposal.amount,
+			)
+			.map_err(|_| Error::<T>::InsufficientTreasuryBalance)?;
+			T::Currency::unreserve(T::GetNativeCurrencyId::get(), &proposal.proposer, proposal.bond);
+
+			Self::deposit_event(Event::Awarded(
+				proposal_index,
+				proposal.beneficiary,
+				proposal.currency_id,
+				proposal.amount,
+			));
+			Ok(().into())
+		}
+
+		#[pallet::weight(10_000)]
+		#[frame_support::transactional]
+		pub fn reject_proposal(origin: OriginFor<T>, proposal_index: ProposalIndex) -> DispatchResultWithPostInfo {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let proposal = Proposals::<T>::take(proposal_index).ok_or(Error::<T>::InvalidProposalIndex)?;
+
+			// the bond may have been slashed in the meantime, only what is left is moved
+			let remainder = T::Currency::repatriate_reserved(
+				T::GetNativeCurrencyId::get(),
+				&proposal.proposer,
+				&Self::account_id(),
+				proposal.bond,
+				BalanceStatus::Free,
+			)?;
+			let moved = proposal.bond.saturating_sub(remainder);
+
+			Self::deposit_event(Event::Rejected(proposal_index, moved));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Account holding the treasury funds.
+	pub fn account_id() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// Treasury funds of a currency.
+	pub fn balance(currency_id: CurrencyIdOf<T>) -> BalanceOf<T> {
+		T::Currency::free_balance(currency_id, &Self::account_id())
+	}
+}
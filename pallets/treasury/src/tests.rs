@@ -0,0 +1,121 @@
+use super::*;
+use crate::mock::{Event, *};
+use frame_support::{assert_noop, assert_ok};
+
+fn events() -> Vec<Event> {
+    let evt = System::events()
+        .into_iter()
+        .map(|evt| evt.event)
+        .filter(|evt| matches!(evt, Event::pallet_multi_treasury(_)))
+        .collect::<Vec<_>>();
+    System::reset_events();
+    evt
+}
+
+#[test]
+fn test_approve_proposal() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Treasury::propose_spend(Origin::signed(BOB), DOT, 500, BOB),
+            Error::<Test>::InsufficientProposersBalance
+        );
+
+        assert_ok!(Treasury::propose_spend(Origin::signed(ALICE), DOT, 500, BOB));
+        assert_eq!(Currencies::reserved_balance(NATIVE, &ALICE), 100);
+        assert_eq!(
+            Treasury::proposals(0),
+            Some(SpendProposal {
+                proposer: ALICE,
+                beneficiary: BOB,
+                currency_id: DOT,
+                amount: 500,
+                bond: 100,
+            })
+        );
+
+        assert_noop!(
+            Treasury::approve_proposal(Origin::signed(ALICE), 0),
+            frame_support::error::BadOrigin
+        );
+        assert_ok!(Treasury::approve_proposal(Origin::root(), 0));
+        assert_noop!(
+            Treasury::approve_proposal(Origin::root(), 0),
+            Error::<Test>::InvalidProposalIndex
+        );
+
+        assert_eq!(Tokens::free_balance(DOT, &BOB), 500);
+        assert_eq!(Treasury::balance(DOT), 500);
+        assert_eq!(Currencies::free_balance(NATIVE, &ALICE), 1_000);
+        assert_eq!(Currencies::reserved_balance(NATIVE, &ALICE), 0);
+
+        assert_eq!(
+            events().as_slice(),
+            [
+                Event::pallet_multi_treasury(crate::Event::Proposed(0, ALICE, BOB, DOT, 500)),
+                Event::pallet_multi_treasury(crate::Event::Awarded(0, BOB, DOT, 500)),
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_approve_without_funds() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Treasury::propose_spend(Origin::signed(ALICE), DOT, 1_001, BOB));
+        assert_noop!(
+            Treasury::approve_proposal(Origin::root(), 0),
+            Error::<Test>::InsufficientTreasuryBalance
+        );
+        assert!(Treasury::proposals(0).is_some());
+    });
+}
+
+#[test]
+fn test_reject_proposal() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Treasury::propose_spend(Origin::signed(ALICE), DOT, 500, BOB));
+        assert_ok!(Treasury::reject_proposal(Origin::root(), 0));
+
+        assert_eq!(Treasury::proposals(0), None);
+        assert_eq!(Currencies::free_balance(NATIVE, &ALICE), 900);
+        assert_eq!(Currencies::reserved_balance(NATIVE, &ALICE), 0);
+        assert_eq!(Treasury::balance(NATIVE), 101);
+        assert_eq!(Treasury::balance(DOT), 1_000);
+    });
+}
+
+#[test]
+fn test_reject_slashed_proposal() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Treasury::propose_spend(Origin::signed(ALICE), DOT, 500, BOB));
+        assert_eq!(Treasury::proposal_count(), 1);
+
+        //part of the bond is gone, only the rest goes to the treasury
+        assert_eq!(Currencies::slash_reserved(NATIVE, &ALICE, 40), 0);
+        events();
+        assert_ok!(Treasury::reject_proposal(Origin::root(), 0));
+
+        assert_eq!(Currencies::reserved_balance(NATIVE, &ALICE), 0);
+        assert_eq!(Treasury::balance(NATIVE), 61);
+        assert_eq!(
+            events().as_slice(),
+            [Event::pallet_multi_treasury(crate::Event::Rejected(0, 60))]
+        );
+    });
+}
+
+#[test]
+fn test_reject_proposal_to_dead_treasury() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Treasury::propose_spend(Origin::signed(ALICE), DOT, 500, BOB));
+        assert_ok!(Balances::transfer(Origin::signed(Treasury::account_id()), BOB, 1));
+
+        //the bond cannot be moved to a treasury without a native account, so nothing is taken
+        assert_noop!(
+            Treasury::reject_proposal(Origin::root(), 0),
+            pallet_balances::Error::<Test, _>::DeadAccount
+        );
+        assert!(Treasury::proposals(0).is_some());
+        assert_eq!(Currencies::reserved_balance(NATIVE, &ALICE), 100);
+    });
+}
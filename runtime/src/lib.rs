@@ -13,28 +13,33 @@ use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthority
 use serde::{Deserialize, Serialize};
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{
+	crypto::KeyTypeId,
+	u32_trait::{_1, _2, _3, _4},
+	OpaqueMetadata,
+};
 use sp_runtime::traits::{
-	AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify,
+	BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify,
 };
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::Zero,
-	transaction_validity::{TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, MultiSignature, RuntimeDebug,
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
+	ApplyExtrinsicResult, ModuleId, MultiSignature, RuntimeDebug,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 
+use frame_support::{StorageHasher, Twox128};
+use frame_system::{EnsureOneOf, EnsureRoot};
 use orml_currencies::BasicCurrencyAdapter;
 use orml_traits::parameter_type_with_key;
 
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
 	construct_runtime, parameter_types,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{Currency, KeyOwnerProofSystem, OnUnbalanced, Randomness},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
@@ -46,9 +51,19 @@ pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::CurrencyAdapter;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{FixedPointNumber, FixedU128, Perbill, Permill};
 
+pub use pallet_asset_registry;
+pub use pallet_evm_accounts;
 pub use pallet_exchange;
+pub use pallet_fee_payment;
+pub use pallet_multi_treasury;
+pub use pallet_rewards;
+
+mod multiaddress;
+pub use multiaddress::MultiAddressLookup;
+#[cfg(feature = "try-runtime")]
+pub mod try_runtime;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -77,12 +92,20 @@ pub type Hash = sp_core::H256;
 pub type DigestItem = generic::DigestItem<Hash>;
 
 #[derive(Encode, Decode, Eq, PartialEq, Copy, Clone, RuntimeDebug, PartialOrd, Ord)]
-#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, scale_info::TypeInfo))]
 pub enum CurrencyId {
 	Native,
 	DOT,
 	KSM,
 	BTC,
+	/// A token registered in `AssetRegistry`.
+	Token(pallet_asset_registry::TokenId),
+}
+
+impl From<pallet_asset_registry::TokenId> for CurrencyId {
+	fn from(id: pallet_asset_registry::TokenId) -> Self {
+		CurrencyId::Token(id)
+	}
 }
 
 pub type Amount = i128;
@@ -122,10 +145,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 121,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 9,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -143,6 +166,9 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// Native currency has 15 decimals.
+pub const DOLLARS: Balance = 1_000_000_000_000_000;
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -179,7 +205,7 @@ impl frame_system::Config for Runtime {
 	/// The aggregated dispatch type that is available for extrinsics.
 	type Call = Call;
 	/// The lookup mechanism to get account ID from whatever is passed in dispatchers.
-	type Lookup = AccountIdLookup<AccountId, ()>;
+	type Lookup = MultiAddressLookup<Runtime>;
 	/// The index type for storing how many extrinsics an account has signed.
 	type Index = Index;
 	/// The index type for blocks.
@@ -251,6 +277,42 @@ impl pallet_timestamp::Config for Runtime {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"orml/trs");
+	pub TreasuryAccount: AccountId = Treasury::account_id();
+}
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Moves native funds that would otherwise be burned into the treasury account.
+pub struct ToTreasury;
+impl OnUnbalanced<NegativeImbalance> for ToTreasury {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		Balances::resolve_creating(&TreasuryAccount::get(), amount);
+	}
+}
+
+parameter_types! {
+	pub const IndexDeposit: Balance = DOLLARS;
+}
+
+impl pallet_indices::Config for Runtime {
+	type AccountIndex = AccountIndex;
+	type Currency = Balances;
+	type Deposit = IndexDeposit;
+	type Event = Event;
+	type WeightInfo = pallet_indices::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub EvmAccountsPrefix: &'static [u8] = b"Link to orml-workshop account:";
+}
+
+impl pallet_evm_accounts::Config for Runtime {
+	type Event = Event;
+	type Prefix = EvmAccountsPrefix;
+}
+
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
 	pub const MaxLocks: u32 = 50;
@@ -262,7 +324,7 @@ impl pallet_balances::Config for Runtime {
 	type Balance = Balance;
 	/// The ubiquitous event type.
 	type Event = Event;
-	type DustRemoval = ();
+	type DustRemoval = ToTreasury;
 	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 	type WeightInfo = pallet_balances::weights::SubstrateWeight<Runtime>;
@@ -273,15 +335,112 @@ parameter_types! {
 }
 
 impl pallet_transaction_payment::Config for Runtime {
-	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type OnChargeTransaction = CurrencyAdapter<Balances, ToTreasury>;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = IdentityFee<Balance>;
 	type FeeMultiplierUpdate = ();
 }
 
-impl pallet_sudo::Config for Runtime {
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+	pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Config<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+	type MaxMembers = CouncilMaxMembers;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = pallet_collective::weights::SubstrateWeight<Runtime>;
+}
+
+/// Root, or more than half of the council.
+pub type EnsureRootOrHalfCouncil = EnsureOneOf<
+	AccountId,
+	EnsureRoot<AccountId>,
+	pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>,
+>;
+
+impl pallet_membership::Config<pallet_membership::Instance1> for Runtime {
+	type Event = Event;
+	type AddOrigin = EnsureRootOrHalfCouncil;
+	type RemoveOrigin = EnsureRootOrHalfCouncil;
+	type SwapOrigin = EnsureRootOrHalfCouncil;
+	type ResetOrigin = EnsureRootOrHalfCouncil;
+	type PrimeOrigin = EnsureRootOrHalfCouncil;
+	type MembershipInitialized = Council;
+	type MembershipChanged = Council;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * BlockWeights::get().max_block;
+	pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+impl pallet_scheduler::Config for Runtime {
 	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
 	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+	pub const VotingPeriod: BlockNumber = 7 * DAYS;
+	pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+	pub const InstantAllowed: bool = true;
+	pub const MinimumDeposit: Balance = 100 * DOLLARS;
+	pub const EnactmentPeriod: BlockNumber = 2 * DAYS;
+	pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+	pub const PreimageByteDeposit: Balance = DOLLARS / 100;
+	pub const MaxVotes: u32 = 100;
+	pub const MaxProposals: u32 = 100;
+}
+
+impl pallet_democracy::Config for Runtime {
+	type Proposal = Call;
+	type Event = Event;
+	type Currency = Balances;
+	type EnactmentPeriod = EnactmentPeriod;
+	type LaunchPeriod = LaunchPeriod;
+	type VotingPeriod = VotingPeriod;
+	type MinimumDeposit = MinimumDeposit;
+	/// A simple majority of the council can schedule the next external referendum.
+	type ExternalOrigin = pallet_collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	/// A super-majority of the council can make it a majority-carries referendum.
+	type ExternalMajorityOrigin = pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	/// A unanimous council can make it a negative turnout biased referendum.
+	type ExternalDefaultOrigin = pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+	type FastTrackOrigin = pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+	type InstantOrigin = pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+	type InstantAllowed = InstantAllowed;
+	type FastTrackVotingPeriod = FastTrackVotingPeriod;
+	type CancellationOrigin = pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+	type CancelProposalOrigin = EnsureOneOf<
+		AccountId,
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>,
+	>;
+	type BlacklistOrigin = EnsureRoot<AccountId>;
+	type VetoOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
+	type CooloffPeriod = CooloffPeriod;
+	type PreimageByteDeposit = PreimageByteDeposit;
+	type OperationalPreimageOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
+	type Slash = ToTreasury;
+	type Scheduler = Scheduler;
+	type PalletsOrigin = OriginCaller;
+	type MaxVotes = MaxVotes;
+	type WeightInfo = pallet_democracy::weights::SubstrateWeight<Runtime>;
+	type MaxProposals = MaxProposals;
 }
 
 impl orml_tokens::Config for Runtime {
@@ -291,12 +450,12 @@ impl orml_tokens::Config for Runtime {
 	type CurrencyId = CurrencyId;
 	type WeightInfo = ();
 	type ExistentialDeposits = ExistentialDeposits;
-	type OnDust = ();
+	type OnDust = orml_tokens::TransferDust<Runtime, TreasuryAccount>;
 }
 
 parameter_type_with_key! {
-	pub ExistentialDeposits: |_currency_id: CurrencyId| -> Balance {
-		Zero::zero()
+	pub ExistentialDeposits: |currency_id: CurrencyId| -> Balance {
+		AssetRegistry::existential_deposit(*currency_id)
 	};
 }
 
@@ -312,11 +471,112 @@ impl orml_currencies::Config for Runtime {
 	type WeightInfo = ();
 }
 
+impl pallet_asset_registry::Config for Runtime {
+	type Event = Event;
+	type CurrencyId = CurrencyId;
+	type Balance = Balance;
+	type RegisterOrigin = EnsureRootOrHalfCouncil;
+	type Currency = Currencies;
+}
+
+parameter_type_with_key! {
+	pub MinimumOrderSizes: |currency_id: CurrencyId| -> Balance {
+		AssetRegistry::min_order_size(*currency_id)
+	};
+}
+
+parameter_type_with_key! {
+	pub CircuitBreakerMinimumSizes: |currency_id: CurrencyId| -> Balance {
+		AssetRegistry::min_order_size(*currency_id).saturating_mul(10)
+	};
+}
+
+parameter_types! {
+	pub const BlocksPerDay: BlockNumber = DAYS;
+	pub const CircuitBreakerThreshold: Permill = Permill::from_percent(20);
+	pub const CircuitBreakerDuration: BlockNumber = 10 * MINUTES;
+	pub const ExchangeUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_exchange::Config for Runtime {
 	type Event = Event;
 	type Currency = Currencies;
 	type OrderId = u32;
+	type OnOrderCreated = ();
+	type OnOrderFilled = Rewards;
+	type OnOrderCancelled = ();
+	type UpdateOrigin = EnsureRootOrHalfCouncil;
+	type FeeCollector = TreasuryAccount;
+	type BlocksPerDay = BlocksPerDay;
+	type PauseOrigin = EnsureRootOrHalfCouncil;
+	type CircuitBreakerThreshold = CircuitBreakerThreshold;
+	type CircuitBreakerDuration = CircuitBreakerDuration;
+	type MinimumOrderSize = MinimumOrderSizes;
+	type CircuitBreakerMinimumSize = CircuitBreakerMinimumSizes;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as Verify>::Signer;
+	type UnsignedPriority = ExchangeUnsignedPriority;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+parameter_types! {
+	pub const RewardPeriod: BlockNumber = DAYS;
+	pub const RewardsModuleId: ModuleId = ModuleId(*b"orml/rwd");
+}
+
+impl pallet_rewards::Config for Runtime {
+	type Event = Event;
+	type Currency = Currencies;
+	type GetNativeCurrencyId = GetNativeCurrencyId;
+	type RewardPeriod = RewardPeriod;
+	type UpdateOrigin = EnsureRootOrHalfCouncil;
+	type ModuleId = RewardsModuleId;
+}
+
+/// Converts fees at the reference price of the exchange between the native currency and the fee
+/// currency, which only fills between different accounts above the circuit breaker's minimum size
+/// move. `FeePayment` clamps it to the bounds set by governance.
+pub struct ExchangeNativeRate;
+impl pallet_fee_payment::NativeRate<CurrencyId> for ExchangeNativeRate {
+	fn native_rate(currency_id: CurrencyId) -> Option<FixedU128> {
+		let pair = Exchange::trading_pair(CurrencyId::Native, currency_id);
+		let price = Exchange::reference_prices(pair)?;
+		if pair.0 == CurrencyId::Native {
+			Some(price)
+		} else {
+			price.reciprocal()
+		}
+	}
+}
+
+impl pallet_fee_payment::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type GetNativeCurrencyId = GetNativeCurrencyId;
+	type NativeRate = ExchangeNativeRate;
+	type FeeCollector = TreasuryAccount;
+	type UpdateOrigin = EnsureRootOrHalfCouncil;
+}
+
+parameter_types! {
+	pub const ProposalBond: Balance = 100 * DOLLARS;
+}
+
+impl pallet_multi_treasury::Config for Runtime {
+	type Event = Event;
+	type Currency = Currencies;
+	type GetNativeCurrencyId = GetNativeCurrencyId;
+	type ProposalBond = ProposalBond;
+	type ApproveOrigin = EnsureRootOrHalfCouncil;
+	type ModuleId = TreasuryModuleId;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -326,6 +586,8 @@ construct_runtime!(
 		NodeBlock = opaque::Block,
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
+		// The index of a pallet is its position, which calls and events are encoded with: new
+		// pallets are appended, and removed ones leave their index unused.
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
 		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
@@ -333,17 +595,29 @@ construct_runtime!(
 		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
-		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		// 7 was Sudo
 
-		Currencies: orml_currencies::{Module, Call, Event<T>},
+		Currencies: orml_currencies::{Module, Call, Event<T>} = 8,
 		Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
 
-		Exchange: pallet_exchange::{Module, Storage, Call, Event<T>},
+		Exchange: pallet_exchange::{Module, Storage, Call, Event<T>, Config<T>, ValidateUnsigned},
+		Rewards: pallet_rewards::{Module, Storage, Call, Event<T>},
+		AssetRegistry: pallet_asset_registry::{Module, Call, Storage, Event<T>, Config<T>},
+		FeePayment: pallet_fee_payment::{Module, Call, Storage, Event<T>},
+		Treasury: pallet_multi_treasury::{Module, Call, Storage, Event<T>},
+
+		Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+		CouncilMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
+		Scheduler: pallet_scheduler::{Module, Call, Storage, Event<T>},
+		Democracy: pallet_democracy::{Module, Call, Storage, Config, Event<T>},
+
+		Indices: pallet_indices::{Module, Call, Storage, Config<T>, Event<T>},
+		EvmAccounts: pallet_evm_accounts::{Module, Call, Storage, Event<T>},
 	}
 );
 
 /// The address format for describing accounts.
-pub type Address = sp_runtime::MultiAddress<AccountId, ()>;
+pub type Address = sp_runtime::MultiAddress<AccountId, AccountIndex>;
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
 /// Block type as expected by this runtime.
@@ -360,7 +634,7 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
+	pallet_fee_payment::ChargeFeeIn<Runtime>
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -373,8 +647,18 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllModules,
+	RemoveSudo,
 >;
 
+/// Removes the storage left by `pallet_sudo`, replaced by council and democracy governance.
+pub struct RemoveSudo;
+impl frame_support::traits::OnRuntimeUpgrade for RemoveSudo {
+	fn on_runtime_upgrade() -> Weight {
+		frame_support::storage::unhashed::kill_prefix(&Twox128::hash(b"Sudo"));
+		RocksDbWeight::get().writes(1)
+	}
+}
+
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -505,6 +789,19 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(feature = "try-runtime")]
+	impl try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade() -> (Weight, Weight) {
+			use frame_support::traits::OnRuntimeUpgrade;
+
+			// the hooks `Executive` runs in the first block after an upgrade, in the same order
+			let weight = <frame_system::Module<Runtime> as OnRuntimeUpgrade>::on_runtime_upgrade()
+				.saturating_add(RemoveSudo::on_runtime_upgrade())
+				.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade());
+			(weight, BlockWeights::get().max_block)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -546,3 +843,47 @@ impl_runtime_apis! {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::traits::{OnRuntimeUpgrade, PalletInfo as _};
+
+	#[test]
+	fn test_pallet_indices() {
+		// pallets after the removed Sudo keep their index
+		assert_eq!(PalletInfo::index::<TransactionPayment>(), Some(6));
+		assert_eq!(PalletInfo::index::<Currencies>(), Some(8));
+		assert_eq!(PalletInfo::index::<Exchange>(), Some(10));
+	}
+
+	#[test]
+	fn test_remove_sudo() {
+		let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into();
+		ext.execute_with(|| {
+			let key = [Twox128::hash(b"Sudo"), Twox128::hash(b"Key")].concat();
+			frame_support::storage::unhashed::put(&key, &AccountId::from([1u8; 32]));
+
+			RemoveSudo::on_runtime_upgrade();
+			assert!(!frame_support::storage::unhashed::exists(&key));
+		});
+	}
+
+	#[cfg(feature = "try-runtime")]
+	#[test]
+	fn test_try_runtime_upgrade() {
+		use try_runtime::runtime_decl_for_TryRuntime::TryRuntime;
+
+		let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into();
+		ext.execute_with(|| {
+			let (weight, max_block) = <Runtime as TryRuntime<Block>>::on_runtime_upgrade();
+			assert!(weight > 0 && weight <= max_block);
+		});
+	}
+}
@@ -1,90 +1,85 @@
-// This file is part of Substrate.
+//! Lookup of the runtime's `MultiAddress` formats.
+//!
+//! Besides full account ids, accounts can be addressed by their `pallet_indices` index, a
+//! compact number assigned on claim, by the raw 32 bytes of the account id, or by an Ethereum
+//! address linked with `pallet_evm_accounts`.
 
-// Copyright (C) 2017-2021 Parity Technologies (UK) Ltd.
-// SPDX-License-Identifier: Apache-2.0
+use sp_core::H160;
+use sp_runtime::{
+	traits::{LookupError, StaticLookup},
+	MultiAddress,
+};
+use sp_std::marker::PhantomData;
 
-// Licensed under the Apache License, Version 2.0 (the "License");
-// you may not use this file except in compliance with the License.
-// You may obtain a copy of the License at
-//
-// 	http://www.apache.org/licenses/LICENSE-2.0
-//
-// Unless required by applicable law or agreed to in writing, software
-// distributed under the License is distributed on an "AS IS" BASIS,
-// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
-// See the License for the specific language governing permissions and
-// limitations under the License.
+/// A lookup resolving `Id`, `Index` through `pallet_indices`, `Address32` by conversion and
+/// `Address20` through `pallet_evm_accounts`.
+pub struct MultiAddressLookup<T>(PhantomData<T>);
 
-//! MultiAddress type is a wrapper for multiple downstream account formats.
-//! Copied from `sp_runtime::multiaddress`, and can be removed and replaced when
-//! updating to a newer version of Substrate.
-
-use codec::{Encode, Decode, Codec};
-use sp_std::{vec::Vec, marker::PhantomData, fmt::Debug};
-use sp_runtime::{RuntimeDebug, traits::{LookupError, StaticLookup}};
-
-/// A multi-format address wrapper for on-chain accounts.
-#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug)]
-#[cfg_attr(feature = "std", derive(Hash))]
-pub enum MultiAddress<AccountId, AccountIndex> {
-	/// It's an account ID (pubkey).
-	Id(AccountId),
-	/// It's an account index.
-	Index(#[codec(compact)] AccountIndex),
-	/// It's some arbitrary raw bytes.
-	Raw(Vec<u8>),
-	/// It's a 32 byte representation.
-	Address32([u8; 32]),
-	/// Its a 20 byte representation.
-	Address20([u8; 20]),
-}
-
-#[cfg(feature = "std")]
-impl<AccountId, AccountIndex> std::fmt::Display for MultiAddress<AccountId, AccountIndex>
+impl<T> StaticLookup for MultiAddressLookup<T>
 where
-	AccountId: std::fmt::Debug,
-	AccountIndex: std::fmt::Debug,
+	T: pallet_indices::Config + pallet_evm_accounts::Config,
+	T::AccountId: From<[u8; 32]>,
 {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		use sp_core::hexdisplay::HexDisplay;
-		match self {
-			MultiAddress::Raw(inner) => write!(f, "MultiAddress::Raw({})", HexDisplay::from(inner)),
-			MultiAddress::Address32(inner) => write!(f, "MultiAddress::Address32({})", HexDisplay::from(inner)),
-			MultiAddress::Address20(inner) => write!(f, "MultiAddress::Address20({})", HexDisplay::from(inner)),
-			_ => write!(f, "{:?}", self),
+	type Source = MultiAddress<T::AccountId, T::AccountIndex>;
+	type Target = T::AccountId;
+
+	fn lookup(address: Self::Source) -> Result<Self::Target, LookupError> {
+		match address {
+			MultiAddress::Id(id) => Ok(id),
+			MultiAddress::Index(index) => pallet_indices::Module::<T>::lookup_index(index).ok_or(LookupError),
+			MultiAddress::Address32(bytes) => Ok(bytes.into()),
+			MultiAddress::Address20(bytes) => {
+				pallet_evm_accounts::Pallet::<T>::account_of(H160::from(bytes)).ok_or(LookupError)
+			}
+			MultiAddress::Raw(_) => Err(LookupError),
 		}
 	}
-}
 
-impl<AccountId, AccountIndex> From<AccountId> for MultiAddress<AccountId, AccountIndex> {
-	fn from(a: AccountId) -> Self {
-		MultiAddress::Id(a)
+	fn unlookup(id: Self::Target) -> Self::Source {
+		MultiAddress::Id(id)
 	}
 }
 
-impl<AccountId: Default, AccountIndex> Default for MultiAddress<AccountId, AccountIndex> {
-	fn default() -> Self {
-		MultiAddress::Id(Default::default())
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{AccountId, Balances, Call, Origin, Runtime, DOLLARS};
+	use frame_support::{assert_ok, traits::Currency};
+	use sp_runtime::traits::Dispatchable;
+
+	type Lookup = MultiAddressLookup<Runtime>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into()
 	}
-}
 
-/// A lookup implementation returning the `AccountId` from a `MultiAddress`.
-pub struct AccountIdLookup<AccountId, AccountIndex>(PhantomData<(AccountId, AccountIndex)>);
-impl<AccountId, AccountIndex> StaticLookup for AccountIdLookup<AccountId, AccountIndex>
-where
-	AccountId: Codec + Clone + PartialEq + Debug,
-	AccountIndex: Codec + Clone + PartialEq + Debug,
-	MultiAddress<AccountId, AccountIndex>: Codec,
-{
-	type Source = MultiAddress<AccountId, AccountIndex>;
-	type Target = AccountId;
-	fn lookup(x: Self::Source) -> Result<Self::Target, LookupError> {
-		match x {
-			MultiAddress::Id(i) => Ok(i),
-			_ => Err(LookupError),
-		}
+	#[test]
+	fn test_lookup_id_and_bytes() {
+		new_test_ext().execute_with(|| {
+			let alice = AccountId::from([1u8; 32]);
+
+			assert_eq!(Lookup::lookup(MultiAddress::Id(alice.clone())).ok(), Some(alice.clone()));
+			assert_eq!(Lookup::lookup(MultiAddress::Address32([1u8; 32])).ok(), Some(alice.clone()));
+			assert_eq!(Lookup::unlookup(alice.clone()), MultiAddress::Id(alice));
+			// raw bytes of any length are ambiguous
+			assert!(Lookup::lookup(MultiAddress::Raw(vec![1u8; 32])).is_err());
+			assert!(Lookup::lookup(MultiAddress::Address20([1u8; 20])).is_err());
+		});
 	}
-	fn unlookup(x: Self::Target) -> Self::Source {
-		MultiAddress::Id(x)
+
+	#[test]
+	fn test_lookup_index() {
+		new_test_ext().execute_with(|| {
+			let alice = AccountId::from([1u8; 32]);
+			assert!(Lookup::lookup(MultiAddress::Index(7)).is_err());
+
+			Balances::make_free_balance_be(&alice, 10 * DOLLARS);
+			assert_ok!(Call::Indices(pallet_indices::Call::claim(7)).dispatch(Origin::signed(alice.clone())));
+			assert_eq!(Lookup::lookup(MultiAddress::Index(7)).ok(), Some(alice));
+			assert!(Lookup::lookup(MultiAddress::Index(8)).is_err());
+		});
 	}
 }
@@ -0,0 +1,15 @@
+//! Runtime API of the node's `try-runtime` subcommand.
+//!
+//! Substrate 3.0 has no `frame-try-runtime`, so the runtime declares the API itself, with the
+//! same `on_runtime_upgrade` as the upstream one.
+
+use frame_support::weights::Weight;
+
+sp_api::decl_runtime_apis! {
+	/// Dry runs of the runtime upgrade against the state of a block.
+	pub trait TryRuntime {
+		/// Run the hooks of a runtime upgrade with their `try-runtime` checks, panicking if one
+		/// fails, and return their weight and the maximum weight of a block.
+		fn on_runtime_upgrade() -> (Weight, Weight);
+	}
+}